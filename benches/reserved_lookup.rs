@@ -0,0 +1,59 @@
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use chia_compiler::chia::{
+    lang::{find_reserved_token, reserved_tokens},
+    lexer::Lexer,
+};
+use chia_compiler::common::reserved::ReservedToken;
+
+const SAMPLE_LINE: &str = "static mut i32 value = (count << 2) >= limit ? 0x1F : total / 3;\n";
+const LINES: usize = 50_000;
+
+// The lookup used before the reserved table was compiled, kept as a baseline.
+fn find_reserved_token_linear(s: &str) -> Option<&'static ReservedToken<'static>> {
+    reserved_tokens().iter().find(|token| match token {
+        ReservedToken::Char(c) => s.len() == c.len_utf8() && s.starts_with(*c),
+        ReservedToken::Operator(op, _) => *op == s,
+        ReservedToken::Keyword(keyword) => *keyword == s,
+    })
+}
+
+fn time(name: &str, mut f: impl FnMut()) -> Duration {
+    let start = Instant::now();
+    f();
+    let elapsed = start.elapsed();
+    println!("{:<28} {:>10.2?}", name, elapsed);
+    elapsed
+}
+
+fn main() {
+    let src_code = SAMPLE_LINE.repeat(LINES);
+    let words: Vec<&str> = src_code
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|word| !word.is_empty())
+        .collect();
+
+    let linear = time("linear reserved lookup", || {
+        for word in &words {
+            black_box(find_reserved_token_linear(black_box(word)));
+        }
+    });
+    let compiled = time("compiled reserved lookup", || {
+        for word in &words {
+            black_box(find_reserved_token(black_box(word)));
+        }
+    });
+    println!(
+        "speedup: {:.1}x over {} lookups",
+        linear.as_secs_f64() / compiled.as_secs_f64(),
+        words.len()
+    );
+
+    time("lex 50k lines", || {
+        let mut lexer = Lexer::new(&src_code);
+        while let Ok(Some(token)) = lexer.next_token() {
+            black_box(token);
+        }
+    });
+}
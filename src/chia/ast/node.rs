@@ -1,6 +1,8 @@
-use crate::common::{reserved::ReservedToken, token::Token};
+use crate::common::{position::PositionRange, reserved::ReservedToken, token::Token};
+use std::fmt;
 use std::vec::Vec;
 
+#[allow(clippy::vec_box)]
 pub struct ProgramInfo<'a, 'b> {
     name: String,
     var_fn_defs: Vec<Box<ASTNode<'a, 'b>>>,
@@ -10,6 +12,10 @@ impl<'a, 'b> ProgramInfo<'a, 'b> {
     pub fn new(name: String, var_fn_defs: Vec<Box<ASTNode<'a, 'b>>>) -> ProgramInfo<'a, 'b> {
         ProgramInfo { name, var_fn_defs }
     }
+
+    pub fn definitions(&self) -> &[Box<ASTNode<'a, 'b>>] {
+        &self.var_fn_defs
+    }
 }
 
 pub struct TypeDef<'a, 'b> {
@@ -20,8 +26,8 @@ pub struct TypeDef<'a, 'b> {
 impl<'a, 'b> TypeDef<'a, 'b> {
     pub fn new(type_name: &'a Token<'b>, definition: Box<ASTNode<'a, 'b>>) -> TypeDef<'a, 'b> {
         TypeDef {
-            type_name: type_name,
-            definition: definition,
+            type_name,
+            definition,
         }
     }
 }
@@ -51,11 +57,27 @@ impl<'a, 'b> EnumDef<'a, 'b> {
     }
 }
 
+pub struct EnumVariant<'a, 'b> {
+    identifier: Box<ASTNode<'a, 'b>>,
+    value: Option<Box<ASTNode<'a, 'b>>>,
+}
+
+impl<'a, 'b> EnumVariant<'a, 'b> {
+    pub fn new(
+        identifier: Box<ASTNode<'a, 'b>>,
+        value: Option<Box<ASTNode<'a, 'b>>>,
+    ) -> EnumVariant<'a, 'b> {
+        EnumVariant { identifier, value }
+    }
+}
+
 pub struct TypeInfo<'a, 'b> {
     is_static: bool,
     is_mut: bool,
     is_volatile: bool,
     is_pointer: bool,
+    // The number of elements when this is an array of `base_type`.
+    array_length: Option<Box<ASTNode<'a, 'b>>>,
     base_type: Box<ASTNode<'a, 'b>>,
 }
 
@@ -72,6 +94,21 @@ impl<'a, 'b> TypeInfo<'a, 'b> {
             is_mut,
             is_volatile,
             is_pointer,
+            array_length: None,
+            base_type,
+        }
+    }
+
+    pub fn new_array(
+        array_length: Box<ASTNode<'a, 'b>>,
+        base_type: Box<ASTNode<'a, 'b>>,
+    ) -> TypeInfo<'a, 'b> {
+        TypeInfo {
+            is_static: false,
+            is_mut: false,
+            is_volatile: false,
+            is_pointer: false,
+            array_length: Some(array_length),
             base_type,
         }
     }
@@ -112,25 +149,24 @@ impl<'a, 'b> FnDef<'a, 'b> {
             return_type,
             identifier,
             arguments,
-            body: body,
+            body,
         }
     }
 }
 
+#[allow(clippy::vec_box)]
 pub struct FnCall<'a, 'b> {
-    fn_identifier: Box<ASTNode<'a, 'b>>,
+    // Any expression that evaluates to a function, such as `f` or `obj.method`.
+    callee: Box<ASTNode<'a, 'b>>,
     arguments: Vec<Box<ASTNode<'a, 'b>>>,
 }
 
 impl<'a, 'b> FnCall<'a, 'b> {
     pub fn new(
-        fn_identifier: Box<ASTNode<'a, 'b>>,
+        callee: Box<ASTNode<'a, 'b>>,
         arguments: Vec<Box<ASTNode<'a, 'b>>>,
     ) -> FnCall<'a, 'b> {
-        FnCall {
-            fn_identifier,
-            arguments,
-        }
+        FnCall { callee, arguments }
     }
 }
 
@@ -161,7 +197,7 @@ pub enum ControlFlowType {
 
 pub struct ControlFlowInfo<'a, 'b> {
     control_type: ControlFlowType,
-    condition: Box<ASTNode<'a, 'b>>,
+    condition: Option<Box<ASTNode<'a, 'b>>>,
     sequence: Box<ASTNode<'a, 'b>>,
     next_flow: Option<Box<ASTNode<'a, 'b>>>,
 }
@@ -169,7 +205,7 @@ pub struct ControlFlowInfo<'a, 'b> {
 impl<'a, 'b> ControlFlowInfo<'a, 'b> {
     pub fn new(
         control_type: ControlFlowType,
-        condition: Box<ASTNode<'a, 'b>>,
+        condition: Option<Box<ASTNode<'a, 'b>>>,
         sequence: Box<ASTNode<'a, 'b>>,
         next_flow: Option<Box<ASTNode<'a, 'b>>>,
     ) -> ControlFlowInfo<'a, 'b> {
@@ -182,23 +218,66 @@ impl<'a, 'b> ControlFlowInfo<'a, 'b> {
     }
 }
 
-pub enum ASTNode<'a, 'b> {
+pub struct ForLoopInfo<'a, 'b> {
+    init: Option<Box<ASTNode<'a, 'b>>>,
+    condition: Option<Box<ASTNode<'a, 'b>>>,
+    step: Option<Box<ASTNode<'a, 'b>>>,
+    sequence: Box<ASTNode<'a, 'b>>,
+}
+
+impl<'a, 'b> ForLoopInfo<'a, 'b> {
+    pub fn new(
+        init: Option<Box<ASTNode<'a, 'b>>>,
+        condition: Option<Box<ASTNode<'a, 'b>>>,
+        step: Option<Box<ASTNode<'a, 'b>>>,
+        sequence: Box<ASTNode<'a, 'b>>,
+    ) -> ForLoopInfo<'a, 'b> {
+        ForLoopInfo {
+            init,
+            condition,
+            step,
+            sequence,
+        }
+    }
+}
+
+pub enum ASTNodeKind<'a, 'b> {
     Program(ProgramInfo<'a, 'b>),
     TypeDef(TypeDef<'a, 'b>),
     StructDef(StructDef<'a, 'b>),
     EnumDef(EnumDef<'a, 'b>),
+    EnumVariant(EnumVariant<'a, 'b>),
     Type(TypeInfo<'a, 'b>),
     Tuple(Vec<Box<ASTNode<'a, 'b>>>),
     Number(&'a Token<'a>),
     String(&'a Token<'a>),
     Char(&'a Token<'a>),
     Identifier(&'a Token<'a>),
+    Error(&'a Token<'a>),
     Expression(Box<ASTNode<'a, 'b>>),
     Function(FnDef<'a, 'b>),
     FunctionCall(FnCall<'a, 'b>),
+    // `object.field`
+    FieldAccess(Box<ASTNode<'a, 'b>>, Box<ASTNode<'a, 'b>>),
+    // `{1, 2, 3}` or `{.x = 1, .y = 2}`
+    InitializerList(Vec<Box<ASTNode<'a, 'b>>>),
+    // `.field = value` inside an initializer list
+    FieldInitializer(Box<ASTNode<'a, 'b>>, Box<ASTNode<'a, 'b>>),
+    // `scope::name`
+    Path(Box<ASTNode<'a, 'b>>, Box<ASTNode<'a, 'b>>),
+    // `array[index]`
+    Index(Box<ASTNode<'a, 'b>>, Box<ASTNode<'a, 'b>>),
     Variable(VarDef<'a, 'b>),
     Sequence(Vec<Box<ASTNode<'a, 'b>>>),
     ControlFlow(ControlFlowInfo<'a, 'b>),
+    ForLoop(ForLoopInfo<'a, 'b>),
+    Return(Option<Box<ASTNode<'a, 'b>>>),
+    Break,
+    Continue,
+    Dereference(Box<ASTNode<'a, 'b>>),
+    AddressOf(Box<ASTNode<'a, 'b>>),
+    // `(T) x` or `x as T`, holding the target type and then the value.
+    Cast(Box<ASTNode<'a, 'b>>, Box<ASTNode<'a, 'b>>),
     PrefixOperation(&'a ReservedToken<'b>, Box<ASTNode<'a, 'b>>),
     PostfixOperation(&'a ReservedToken<'b>, Box<ASTNode<'a, 'b>>),
     BinaryOperation(
@@ -206,92 +285,483 @@ pub enum ASTNode<'a, 'b> {
         Box<ASTNode<'a, 'b>>,
         Box<ASTNode<'a, 'b>>,
     ),
+    TernaryOperation(
+        &'a ReservedToken<'b>,
+        Box<ASTNode<'a, 'b>>,
+        Box<ASTNode<'a, 'b>>,
+        Box<ASTNode<'a, 'b>>,
+    ),
+}
+
+// A node of the syntax tree along with the source it was parsed from.
+pub struct ASTNode<'a, 'b> {
+    kind: ASTNodeKind<'a, 'b>,
+    span: PositionRange,
 }
 
 impl<'a, 'b> ASTNode<'a, 'b> {
-    pub fn new_program(info: ProgramInfo<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::Program(info)
+    pub fn new(kind: ASTNodeKind<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        ASTNode { kind, span }
+    }
+
+    pub fn kind(&self) -> &ASTNodeKind<'a, 'b> {
+        &self.kind
+    }
+
+    pub fn span(&self) -> &PositionRange {
+        &self.span
     }
 
-    pub fn new_type_def(type_def: TypeDef<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::TypeDef(type_def)
+    pub fn new_program(info: ProgramInfo<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Program(info), span)
     }
 
-    pub fn new_struct_def(struct_def: StructDef<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::StructDef(struct_def)
+    pub fn new_type_def(type_def: TypeDef<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::TypeDef(type_def), span)
     }
 
-    pub fn new_enum_def(enum_def: EnumDef<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::EnumDef(enum_def)
+    pub fn new_struct_def(struct_def: StructDef<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::StructDef(struct_def), span)
     }
 
-    pub fn new_type(type_info: TypeInfo<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::Type(type_info)
+    pub fn new_enum_def(enum_def: EnumDef<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::EnumDef(enum_def), span)
     }
 
-    pub fn new_tuple(content: Vec<Box<ASTNode<'a, 'b>>>) -> ASTNode<'a, 'b> {
-        Self::Tuple(content)
+    pub fn new_enum_variant(variant: EnumVariant<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::EnumVariant(variant), span)
     }
 
-    pub fn new_number(token: &'a Token<'a>) -> ASTNode<'a, 'b> {
-        Self::Number(token)
+    pub fn new_type(type_info: TypeInfo<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Type(type_info), span)
     }
 
-    pub fn new_string(token: &'a Token<'a>) -> ASTNode<'a, 'b> {
-        Self::String(token)
+    pub fn new_tuple(content: Vec<Box<ASTNode<'a, 'b>>>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Tuple(content), span)
     }
 
-    pub fn new_char(token: &'a Token<'a>) -> ASTNode<'a, 'b> {
-        Self::Char(token)
+    pub fn new_number(token: &'a Token<'a>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Number(token), span)
     }
 
-    pub fn new_identifier(token: &'a Token<'a>) -> ASTNode<'a, 'b> {
-        Self::Identifier(token)
+    pub fn new_string(token: &'a Token<'a>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::String(token), span)
     }
 
-    pub fn new_expression(child: Box<ASTNode<'a, 'b>>) -> ASTNode<'a, 'b> {
-        Self::Expression(child)
+    pub fn new_char(token: &'a Token<'a>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Char(token), span)
     }
 
-    pub fn new_function(def: FnDef<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::Function(def)
+    pub fn new_identifier(token: &'a Token<'a>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Identifier(token), span)
     }
 
-    pub fn new_function_call(call: FnCall<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::FunctionCall(call)
+    pub fn new_error(token: &'a Token<'a>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Error(token), span)
     }
 
-    pub fn new_variable(def: VarDef<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::Variable(def)
+    pub fn new_expression(child: Box<ASTNode<'a, 'b>>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Expression(child), span)
     }
 
-    pub fn new_sequence(children: Vec<Box<ASTNode<'a, 'b>>>) -> ASTNode<'a, 'b> {
-        Self::Sequence(children)
+    pub fn new_function(def: FnDef<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Function(def), span)
     }
 
-    pub fn new_control_flow(info: ControlFlowInfo<'a, 'b>) -> ASTNode<'a, 'b> {
-        Self::ControlFlow(info)
+    pub fn new_function_call(call: FnCall<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::FunctionCall(call), span)
+    }
+
+    pub fn new_field_access(
+        object: Box<ASTNode<'a, 'b>>,
+        field: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::FieldAccess(object, field), span)
+    }
+
+    pub fn new_initializer_list(
+        items: Vec<Box<ASTNode<'a, 'b>>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::InitializerList(items), span)
+    }
+
+    pub fn new_field_initializer(
+        field: Box<ASTNode<'a, 'b>>,
+        value: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::FieldInitializer(field, value), span)
+    }
+
+    pub fn new_path(
+        scope: Box<ASTNode<'a, 'b>>,
+        name: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Path(scope, name), span)
+    }
+
+    pub fn new_index(
+        array: Box<ASTNode<'a, 'b>>,
+        index: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Index(array, index), span)
+    }
+
+    pub fn new_variable(def: VarDef<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Variable(def), span)
+    }
+
+    pub fn new_sequence(
+        children: Vec<Box<ASTNode<'a, 'b>>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Sequence(children), span)
+    }
+
+    pub fn new_control_flow(info: ControlFlowInfo<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::ControlFlow(info), span)
+    }
+
+    pub fn new_for_loop(info: ForLoopInfo<'a, 'b>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::ForLoop(info), span)
+    }
+
+    pub fn new_return(value: Option<Box<ASTNode<'a, 'b>>>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Return(value), span)
+    }
+
+    pub fn new_break(span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Break, span)
+    }
+
+    pub fn new_continue(span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Continue, span)
+    }
+
+    pub fn new_dereference(pointer: Box<ASTNode<'a, 'b>>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Dereference(pointer), span)
+    }
+
+    pub fn new_address_of(value: Box<ASTNode<'a, 'b>>, span: PositionRange) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::AddressOf(value), span)
+    }
+
+    pub fn new_cast(
+        target_type: Box<ASTNode<'a, 'b>>,
+        value: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(ASTNodeKind::Cast(target_type, value), span)
     }
 
     pub fn new_prefix_operation(
         operator: &'a ReservedToken<'b>,
         operand: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
     ) -> ASTNode<'a, 'b> {
-        Self::PrefixOperation(operator, operand)
+        Self::new(ASTNodeKind::PrefixOperation(operator, operand), span)
     }
 
     pub fn new_postfix_operation(
         operator: &'a ReservedToken<'b>,
         operand: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
     ) -> ASTNode<'a, 'b> {
-        Self::PostfixOperation(operator, operand)
+        Self::new(ASTNodeKind::PostfixOperation(operator, operand), span)
     }
 
     pub fn new_binary_operation(
         operator: &'a ReservedToken<'b>,
         operand1: Box<ASTNode<'a, 'b>>,
         operand2: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
+    ) -> ASTNode<'a, 'b> {
+        Self::new(
+            ASTNodeKind::BinaryOperation(operator, operand1, operand2),
+            span,
+        )
+    }
+
+    pub fn new_ternary_operation(
+        operator: &'a ReservedToken<'b>,
+        operand1: Box<ASTNode<'a, 'b>>,
+        operand2: Box<ASTNode<'a, 'b>>,
+        operand3: Box<ASTNode<'a, 'b>>,
+        span: PositionRange,
     ) -> ASTNode<'a, 'b> {
-        Self::BinaryOperation(operator, operand1, operand2)
+        Self::new(
+            ASTNodeKind::TernaryOperation(operator, operand1, operand2, operand3),
+            span,
+        )
+    }
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Identifier(s) | Token::DocComment(s) | Token::Error(s) => String::from(*s),
+        Token::Str(info) => String::from(info.raw),
+        Token::Char(info) => String::from(info.raw),
+        Token::Number(info) => {
+            let mut text = String::from(match info.radix {
+                16 => "0x",
+                8 => "0o",
+                2 => "0b",
+                _ => "",
+            });
+            text.push_str(info.whole_number);
+            if let Some(fractional) = info.fractional_part {
+                text.push('.');
+                text.push_str(fractional);
+            }
+            if let Some(exponent) = info.exponent {
+                text.push('e');
+                text.push_str(exponent);
+            }
+            if let Some(suffix) = info.suffix {
+                text.push_str(suffix);
+            }
+            text
+        }
+        Token::Reserved(reserved) => reserved_text(reserved),
+    }
+}
+
+fn reserved_text(token: &ReservedToken) -> String {
+    match token {
+        ReservedToken::Keyword(s) | ReservedToken::Operator(s, _) => String::from(*s),
+        ReservedToken::Char(c) => c.to_string(),
+    }
+}
+
+impl<'a, 'b> TypeVarPair<'a, 'b> {
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        writeln!(f, "{}TypeVarPair", "  ".repeat(depth))?;
+        self.type_of_var.fmt_tree(f, depth + 1)?;
+        self.identifier.fmt_tree(f, depth + 1)
+    }
+}
+
+impl<'a, 'b> ASTNode<'a, 'b> {
+    fn fmt_tree(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+        match &self.kind {
+            ASTNodeKind::Program(info) => {
+                writeln!(f, "{}Program: {}", indent, info.name)?;
+                for def in info.var_fn_defs.iter() {
+                    def.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::TypeDef(def) => {
+                writeln!(f, "{}TypeDef: {}", indent, token_text(def.type_name))?;
+                def.definition.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::StructDef(def) => {
+                writeln!(f, "{}StructDef", indent)?;
+                def.identifier.fmt_tree(f, depth + 1)?;
+                for field in def.fields.iter() {
+                    field.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::EnumDef(def) => {
+                writeln!(f, "{}EnumDef", indent)?;
+                def.identifier.fmt_tree(f, depth + 1)?;
+                for field in def.fields.iter() {
+                    field.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::EnumVariant(variant) => {
+                writeln!(f, "{}EnumVariant", indent)?;
+                variant.identifier.fmt_tree(f, depth + 1)?;
+                if let Some(value) = &variant.value {
+                    value.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::Type(info) => {
+                let mut qualifiers = Vec::new();
+                if info.is_static {
+                    qualifiers.push("static");
+                }
+                if info.is_mut {
+                    qualifiers.push("mut");
+                }
+                if info.is_volatile {
+                    qualifiers.push("volatile");
+                }
+                if info.is_pointer {
+                    qualifiers.push("pointer");
+                }
+                if info.array_length.is_some() {
+                    qualifiers.push("array");
+                }
+                match qualifiers.is_empty() {
+                    true => writeln!(f, "{}Type", indent)?,
+                    _ => writeln!(f, "{}Type ({})", indent, qualifiers.join(", "))?,
+                }
+                info.base_type.fmt_tree(f, depth + 1)?;
+                if let Some(length) = &info.array_length {
+                    length.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::Tuple(children) => {
+                writeln!(f, "{}Tuple", indent)?;
+                for child in children.iter() {
+                    child.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::Number(token) => writeln!(f, "{}Number: {}", indent, token_text(token))?,
+            ASTNodeKind::String(token) => writeln!(f, "{}String: {}", indent, token_text(token))?,
+            ASTNodeKind::Char(token) => writeln!(f, "{}Char: {}", indent, token_text(token))?,
+            ASTNodeKind::Identifier(token) => {
+                writeln!(f, "{}Identifier: {}", indent, token_text(token))?
+            }
+            ASTNodeKind::Error(token) => writeln!(f, "{}Error: {}", indent, token_text(token))?,
+            ASTNodeKind::Expression(child) => {
+                writeln!(f, "{}Expression", indent)?;
+                child.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::Function(def) => {
+                writeln!(f, "{}Function", indent)?;
+                def.return_type.fmt_tree(f, depth + 1)?;
+                def.identifier.fmt_tree(f, depth + 1)?;
+                for arg in def.arguments.iter() {
+                    arg.fmt_tree(f, depth + 1)?;
+                }
+                if let Some(body) = &def.body {
+                    body.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::FunctionCall(call) => {
+                writeln!(f, "{}FunctionCall", indent)?;
+                call.callee.fmt_tree(f, depth + 1)?;
+                for arg in call.arguments.iter() {
+                    arg.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::FieldAccess(object, field) => {
+                writeln!(f, "{}FieldAccess", indent)?;
+                object.fmt_tree(f, depth + 1)?;
+                field.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::InitializerList(items) => {
+                writeln!(f, "{}InitializerList", indent)?;
+                for item in items.iter() {
+                    item.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::FieldInitializer(field, value) => {
+                writeln!(f, "{}FieldInitializer", indent)?;
+                field.fmt_tree(f, depth + 1)?;
+                value.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::Path(scope, name) => {
+                writeln!(f, "{}Path", indent)?;
+                scope.fmt_tree(f, depth + 1)?;
+                name.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::Index(array, index) => {
+                writeln!(f, "{}Index", indent)?;
+                array.fmt_tree(f, depth + 1)?;
+                index.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::Variable(def) => {
+                writeln!(f, "{}Variable", indent)?;
+                def.variable.fmt_tree(f, depth + 1)?;
+                if let Some(value) = &def.value {
+                    value.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::Sequence(children) => {
+                writeln!(f, "{}Sequence", indent)?;
+                for child in children.iter() {
+                    child.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::ControlFlow(info) => {
+                let name = match info.control_type {
+                    ControlFlowType::If => "If",
+                    ControlFlowType::ElseIf => "ElseIf",
+                    ControlFlowType::Else => "Else",
+                    ControlFlowType::While => "While",
+                    ControlFlowType::DoWhile => "DoWhile",
+                    ControlFlowType::Switch => "Switch",
+                    ControlFlowType::SwitchCase => "SwitchCase",
+                    ControlFlowType::SwitchDefault => "SwitchDefault",
+                };
+                writeln!(f, "{}ControlFlow: {}", indent, name)?;
+                if let Some(condition) = &info.condition {
+                    condition.fmt_tree(f, depth + 1)?;
+                }
+                info.sequence.fmt_tree(f, depth + 1)?;
+                if let Some(next) = &info.next_flow {
+                    next.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::ForLoop(info) => {
+                writeln!(f, "{}ForLoop", indent)?;
+                let clauses = [
+                    ("Init", &info.init),
+                    ("Condition", &info.condition),
+                    ("Step", &info.step),
+                ];
+                for (name, clause) in clauses.iter() {
+                    if let Some(clause) = clause {
+                        writeln!(f, "{}  {}", indent, name)?;
+                        clause.fmt_tree(f, depth + 2)?;
+                    }
+                }
+                info.sequence.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::Return(value) => {
+                writeln!(f, "{}Return", indent)?;
+                if let Some(value) = value {
+                    value.fmt_tree(f, depth + 1)?;
+                }
+            }
+            ASTNodeKind::Break => writeln!(f, "{}Break", indent)?,
+            ASTNodeKind::Continue => writeln!(f, "{}Continue", indent)?,
+            ASTNodeKind::Dereference(pointer) => {
+                writeln!(f, "{}Dereference", indent)?;
+                pointer.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::AddressOf(value) => {
+                writeln!(f, "{}AddressOf", indent)?;
+                value.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::Cast(target_type, value) => {
+                writeln!(f, "{}Cast", indent)?;
+                target_type.fmt_tree(f, depth + 1)?;
+                value.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::PrefixOperation(op, operand) => {
+                writeln!(f, "{}PrefixOperation: {}", indent, reserved_text(op))?;
+                operand.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::PostfixOperation(op, operand) => {
+                writeln!(f, "{}PostfixOperation: {}", indent, reserved_text(op))?;
+                operand.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::BinaryOperation(op, operand1, operand2) => {
+                writeln!(f, "{}BinaryOperation: {}", indent, reserved_text(op))?;
+                operand1.fmt_tree(f, depth + 1)?;
+                operand2.fmt_tree(f, depth + 1)?;
+            }
+            ASTNodeKind::TernaryOperation(op, operand1, operand2, operand3) => {
+                writeln!(f, "{}TernaryOperation: {}", indent, reserved_text(op))?;
+                operand1.fmt_tree(f, depth + 1)?;
+                operand2.fmt_tree(f, depth + 1)?;
+                operand3.fmt_tree(f, depth + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a, 'b> fmt::Display for ASTNode<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_tree(f, 0)
     }
 }
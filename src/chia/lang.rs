@@ -1,5 +1,7 @@
-use crate::common::reserved::{OperatorInfo, ReservedToken};
+use crate::common::reserved::{Associativity, OperatorInfo, ReservedToken};
+use std::collections::HashMap;
 use std::option::Option;
+use std::sync::OnceLock;
 
 const MULTIPLICATION_PRECEDENCE: Option<u32> = Some(10);
 const ADDITION_PRECEDENCE: Option<u32> = Some(15);
@@ -14,7 +16,7 @@ const LOGICAL_OR_PRECEDENCE: Option<u32> = Some(55);
 const TERNARY_PRECEDENCE: Option<u32> = Some(60);
 const ASSIGNMENT_PRECEDENCE: Option<u32> = Some(65);
 
-const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
+static CHIA_RESERVED_TOKENS: [ReservedToken; 71] = [
     ReservedToken::Char(';'),
     ReservedToken::Char(':'),
     ReservedToken::Char(','),
@@ -34,6 +36,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -44,6 +47,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -54,6 +58,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -64,6 +69,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -74,6 +80,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -84,6 +91,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -94,6 +102,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -104,6 +113,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: false,
             precedence: None,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -114,6 +124,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -124,6 +135,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: RELATIONAL_EQUAL_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -134,6 +146,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ADDITION_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -144,16 +157,18 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ADDITION_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
         "*",
         OperatorInfo {
-            is_prefix: false,
+            is_prefix: true,
             is_postfix: false,
             is_binary: true,
             is_ternary: false,
             precedence: MULTIPLICATION_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -164,6 +179,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: MULTIPLICATION_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -174,16 +190,18 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: MULTIPLICATION_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
         "&",
         OperatorInfo {
-            is_prefix: false,
+            is_prefix: true,
             is_postfix: false,
             is_binary: true,
             is_ternary: false,
             precedence: BITWISE_AND_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -194,6 +212,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: BITWISE_OR_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -204,6 +223,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: BITWISE_XOR_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -214,6 +234,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: RELATIONAL_UNEQUAL_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -224,6 +245,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: RELATIONAL_UNEQUAL_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -234,6 +256,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: RELATIONAL_UNEQUAL_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -244,6 +267,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -254,6 +278,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: RELATIONAL_UNEQUAL_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -264,6 +289,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -274,6 +300,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: RELATIONAL_EQUAL_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -284,6 +311,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -294,6 +322,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -304,6 +333,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -314,6 +344,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -324,6 +355,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -334,6 +366,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: LOGICAL_OR_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -344,6 +377,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -354,6 +388,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: LOGICAL_AND_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -364,6 +399,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -374,6 +410,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: ASSIGNMENT_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Operator(
@@ -384,6 +421,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: BIT_SHIFT_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -394,6 +432,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: true,
             is_ternary: false,
             precedence: BIT_SHIFT_PRECEDENCE,
+            associativity: Associativity::Left,
         },
     ),
     ReservedToken::Operator(
@@ -404,6 +443,7 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
             is_binary: false,
             is_ternary: true,
             precedence: TERNARY_PRECEDENCE,
+            associativity: Associativity::Right,
         },
     ),
     ReservedToken::Keyword("mut"),
@@ -427,27 +467,115 @@ const CHIA_RESERVED_TOKENS: [ReservedToken; 70] = [
     ReservedToken::Keyword("register"),
     ReservedToken::Keyword("goto"),
     ReservedToken::Keyword("sizeof"),
+    ReservedToken::Keyword("as"),
 ];
 
-pub fn find_reserved_token<'a>(s: &str) -> Option<&'a ReservedToken<'a>> {
-    for token in &CHIA_RESERVED_TOKENS {
-        match token {
-            ReservedToken::Char(c) => {
-                if s.len() == 1 && s.chars().next().unwrap() == *c {
-                    return Some(&token);
-                }
-            }
-            ReservedToken::Operator(op, _) => {
-                if *op == s {
-                    return Some(&token);
-                }
-            }
-            ReservedToken::Keyword(keyword) => {
-                if *keyword == s {
-                    return Some(&token);
+// Reserved chars and operators, keyed char by char for longest-match lookup.
+#[derive(Default)]
+struct ReservedTrieNode {
+    children: HashMap<char, ReservedTrieNode>,
+    token: Option<&'static ReservedToken<'static>>,
+}
+
+struct ReservedTable {
+    symbols: ReservedTrieNode,
+    keywords: HashMap<&'static str, &'static ReservedToken<'static>>,
+}
+
+impl ReservedTable {
+    fn build() -> ReservedTable {
+        let mut table = ReservedTable {
+            symbols: ReservedTrieNode::default(),
+            keywords: HashMap::new(),
+        };
+        for token in &CHIA_RESERVED_TOKENS {
+            let symbol = match token {
+                ReservedToken::Keyword(keyword) => {
+                    table.keywords.insert(keyword, token);
+                    continue;
                 }
+                ReservedToken::Operator(op, _) => op.to_string(),
+                ReservedToken::Char(c) => c.to_string(),
+            };
+            let mut node = &mut table.symbols;
+            for c in symbol.chars() {
+                node = node.children.entry(c).or_default();
             }
+            node.token = Some(token);
         }
+        table
+    }
+}
+
+fn reserved_table() -> &'static ReservedTable {
+    static TABLE: OnceLock<ReservedTable> = OnceLock::new();
+    TABLE.get_or_init(ReservedTable::build)
+}
+
+pub fn reserved_tokens() -> &'static [ReservedToken<'static>] {
+    &CHIA_RESERVED_TOKENS
+}
+
+pub fn find_reserved_token<'a>(s: &str) -> Option<&'a ReservedToken<'a>> {
+    let table = reserved_table();
+    if let Some(token) = table.keywords.get(s) {
+        return Some(*token);
+    }
+    let mut node = &table.symbols;
+    for c in s.chars() {
+        node = node.children.get(&c)?;
+    }
+    node.token
+}
+
+// Finds the longest char or operator at the start of `s`, along with its length in bytes.
+pub fn match_reserved_symbol<'a>(s: &str) -> Option<(&'a ReservedToken<'a>, usize)> {
+    let mut node = &reserved_table().symbols;
+    let mut longest = None;
+    for (idx, c) in s.char_indices() {
+        node = match node.children.get(&c) {
+            Some(child) => child,
+            None => break,
+        };
+        if let Some(token) = node.token {
+            longest = Some((token, idx + c.len_utf8()));
+        }
+    }
+    longest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_reserved_token, match_reserved_symbol, reserved_tokens};
+    use crate::common::{reserved::ReservedToken, token::Token};
+
+    #[test]
+    fn test_find_reserved_token() {
+        for token in reserved_tokens() {
+            let text = match token {
+                ReservedToken::Keyword(keyword) => keyword.to_string(),
+                ReservedToken::Operator(op, _) => op.to_string(),
+                ReservedToken::Char(c) => c.to_string(),
+            };
+            assert!(std::ptr::eq(find_reserved_token(&text).unwrap(), token));
+        }
+        assert!(find_reserved_token("<<<").is_none());
+        assert!(find_reserved_token("whiles").is_none());
+        assert!(find_reserved_token("").is_none());
+    }
+
+    #[test]
+    fn test_match_reserved_symbol() {
+        let (token, len) = match_reserved_symbol("<<= 1").unwrap();
+        assert_eq!(
+            Token::Reserved(token).to_string(),
+            "(Operator, Value: '<<=')"
+        );
+        assert_eq!(len, 3);
+        let (token, len) = match_reserved_symbol("->x").unwrap();
+        assert_eq!(Token::Reserved(token).to_string(), "(Operator, Value: '-')");
+        assert_eq!(len, 1);
+        assert!(match_reserved_symbol("while").is_none());
+        assert!(match_reserved_symbol("").is_none());
     }
-    None
 }
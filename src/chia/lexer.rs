@@ -1,20 +1,49 @@
-use super::lang::find_reserved_token;
+use std::fmt;
+
+use unicode_xid::UnicodeXID;
+
+use super::lang::{find_reserved_token, match_reserved_symbol};
 use crate::common::{
+    diagnostic::{
+        Diagnostic, Severity, INVALID_CHARACTER, INVALID_CHAR_LITERAL, INVALID_ESCAPE,
+        INVALID_NUMBER, UNTERMINATED_TOKEN,
+    },
     position::{Position, PositionRange},
-    token::{NumberInfo, Token},
+    token::{CharInfo, NumberInfo, StrInfo, Token},
 };
 
+const INTEGER_SUFFIXES: [&str; 8] = ["u8", "u16", "u32", "u64", "i8", "i16", "i32", "i64"];
+const FLOAT_SUFFIXES: [&str; 3] = ["f", "f32", "f64"];
+
 pub struct LexerError {
+    code: &'static str,
     description: String,
     position_range: PositionRange,
 }
 
-impl ToString for LexerError {
-    fn to_string(&self) -> String {
-        format!(
+impl LexerError {
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let diagnostic = Diagnostic::new(
+            Severity::Error,
+            self.code,
+            self.description.clone(),
+            Some(self.position_range.clone()),
+        );
+        match self.code {
+            INVALID_ESCAPE => diagnostic.with_help(String::from(
+                "valid escapes are \\n, \\t, \\r, \\0, \\\\, \\', \\\", \\xHH and \\u{...}",
+            )),
+            _ => diagnostic,
+        }
+    }
+}
+
+impl fmt::Display for LexerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "{} (Position: {})",
-            self.description,
-            self.position_range.to_string()
+            self.description, self.position_range
         )
     }
 }
@@ -32,27 +61,41 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // Lexes the rest of the source code, collecting every error instead of stopping at the first.
+    // Each error also leaves an error token in the stream covering the text skipped to recover.
+    pub fn tokenize_all(mut self) -> (Vec<(Token<'a>, PositionRange)>, Vec<LexerError>) {
+        let mut tokens = Vec::new();
+        let mut errors = Vec::new();
+        while let Some(result) = self.next() {
+            match result {
+                Ok(token) => tokens.push(token),
+                Err(err) => {
+                    let text = &self.src_code[err.position_range.start.index..self.position.index];
+                    tokens.push((Token::Error(text), err.position_range.clone()));
+                    errors.push(err);
+                }
+            }
+        }
+        (tokens, errors)
+    }
+
+    fn remaining(&self) -> &'a str {
+        &self.src_code[self.position.index..]
+    }
+
     fn peek(&self) -> Option<char> {
-        self.src_code.chars().nth(self.position.index)
+        self.remaining().chars().next()
     }
 
     fn consume(&mut self) {
         if let Some(c) = self.peek() {
-            match c {
-                '\n' => {
-                    self.position.line += 1;
-                    self.position.column = 0;
-                }
-                _ => {}
-            }
-            self.position.column += 1;
-            self.position.index += 1;
+            self.position.advance(c);
         }
     }
 
     fn read_until(
         &mut self,
-        condition: fn(Option<char>, char) -> bool,
+        mut condition: impl FnMut(Option<char>, char) -> bool,
         until_end: bool,
     ) -> Option<PositionRange> {
         let start_pos = self.position.clone();
@@ -78,30 +121,205 @@ impl<'a> Lexer<'a> {
         None
     }
 
+    // Reads up to the closing `quote`. A literal cannot span an unescaped newline, so an
+    // unterminated one is reported on its own line rather than paired with a later quote.
+    fn read_quoted(&mut self, quote: char) -> Option<PositionRange> {
+        let start_pos = self.position.clone();
+        let mut escaped = false;
+        let range = self.read_until(
+            |_, current| {
+                if escaped {
+                    // `\` before a CRLF line ending escapes both characters.
+                    escaped = current == '\r';
+                    return false;
+                }
+                escaped = current == '\\';
+                current == quote || current == '\n'
+            },
+            false,
+        )?;
+        if !self.src_code[range.end.index..].starts_with(quote) {
+            self.position = start_pos;
+            return None;
+        }
+        Some(range)
+    }
+
+    fn decode_escapes(body: &str, body_start: Position) -> Result<String, LexerError> {
+        let mut value = String::new();
+        let mut position = body_start;
+        let mut chars = body.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\\' {
+                value.push(c);
+                position.advance(c);
+                continue;
+            }
+            let escape_start = position.clone();
+            let mut escape_end = position.clone();
+            position.advance(c);
+            let mut next_char = || {
+                let next = chars.next();
+                if let Some(next) = next {
+                    escape_end = position.clone();
+                    position.advance(next);
+                }
+                next
+            };
+            let decoded = match next_char() {
+                Some('n') => Ok(Some('\n')),
+                Some('t') => Ok(Some('\t')),
+                Some('r') => Ok(Some('\r')),
+                Some('0') => Ok(Some('\0')),
+                Some('\\') => Ok(Some('\\')),
+                Some('\'') => Ok(Some('\'')),
+                Some('\"') => Ok(Some('\"')),
+                Some('x') => {
+                    let digits: String = [next_char(), next_char()].iter().flatten().collect();
+                    match u32::from_str_radix(&digits, 16) {
+                        Ok(code) if digits.len() == 2 && code <= 0x7F => Ok(char::from_u32(code)),
+                        Ok(_) if digits.len() == 2 => {
+                            Err(String::from("A hex escape must be at most '\\x7F'."))
+                        }
+                        _ => Err(String::from(
+                            "A hex escape must be followed by two hexadecimal digits.",
+                        )),
+                    }
+                }
+                Some('u') => {
+                    let mut digits = String::new();
+                    let mut closed = false;
+                    if next_char() == Some('{') {
+                        while let Some(digit) = next_char() {
+                            if digit == '}' {
+                                closed = true;
+                                break;
+                            }
+                            digits.push(digit);
+                            if digits.len() > 6 {
+                                break;
+                            }
+                        }
+                    }
+                    match u32::from_str_radix(&digits, 16).ok().and_then(char::from_u32) {
+                        Some(decoded) if closed => Ok(Some(decoded)),
+                        _ => Err(String::from(
+                            "A unicode escape must have the form '\\u{...}' with 1 to 6 hexadecimal digits of a valid code point.",
+                        )),
+                    }
+                }
+                // A backslash at the end of a line continues the literal on the next line.
+                Some('\n') | Some('\r') => Ok(None),
+                Some(other) => Err(format!("Unknown escape sequence '\\{}'.", other)),
+                None => Err(String::from("Incomplete escape sequence.")),
+            };
+            match decoded {
+                Ok(Some(decoded)) => value.push(decoded),
+                Ok(None) => {
+                    while let Some(&next) = chars.peek() {
+                        if !next.is_whitespace() {
+                            break;
+                        }
+                        position.advance(next);
+                        chars.next();
+                    }
+                }
+                Err(description) => {
+                    return Err(LexerError {
+                        code: INVALID_ESCAPE,
+                        description,
+                        position_range: PositionRange {
+                            start: escape_start,
+                            end: escape_end,
+                        },
+                    })
+                }
+            }
+        }
+        Ok(value)
+    }
+
     fn scan_str(
         &mut self,
         start_pos: Position,
     ) -> Result<Option<(Token<'a>, PositionRange)>, LexerError> {
-        let first_char = self.src_code.chars().nth(start_pos.index);
-        if first_char.is_none() || first_char.unwrap() != '\"' {
+        if !self.src_code[start_pos.index..].starts_with('\"') {
             return Ok(None);
         }
-        if let Some(range) = self.read_until(
-            |last_char, current| {
-                current == '\"' && (last_char.is_none() || last_char.unwrap() != '\\')
-            },
-            false,
-        ) {
+        if let Some(range) = self.read_quoted('\"') {
+            let value = Self::decode_escapes(
+                &self.src_code[range.start.index..range.end.index],
+                range.start,
+            )?;
             return Ok(Some((
-                Token::Str(&self.src_code[start_pos.index..range.end.index + 1]),
+                Token::Str(StrInfo {
+                    raw: &self.src_code[start_pos.index..self.position.index],
+                    value,
+                    is_raw: false,
+                }),
                 PositionRange {
                     start: start_pos,
                     end: range.end,
                 },
             )));
         }
+        self.skip_unterminated_literal();
+        Err(LexerError {
+            code: UNTERMINATED_TOKEN,
+            description: String::from("A string literal must be closed with '\"'."),
+            position_range: PositionRange {
+                start: start_pos.clone(),
+                end: start_pos,
+            },
+        })
+    }
+
+    fn scan_raw_str(&mut self) -> Result<Option<(Token<'a>, PositionRange)>, LexerError> {
+        if self.peek() != Some('r') {
+            return Ok(None);
+        }
+        let hash_count = self.remaining()[1..]
+            .chars()
+            .take_while(|c| *c == '#')
+            .count();
+        let opening = format!("r{}\"", "#".repeat(hash_count));
+        if !self.starts_with(&opening) {
+            return Ok(None);
+        }
+        let start_pos = self.position.clone();
+        for _ in 0..opening.len() {
+            self.consume();
+        }
+        let body_start_pos = self.position.clone();
+        let body_start = body_start_pos.index;
+        let closing = format!("\"{}", "#".repeat(hash_count));
+        while self.peek().is_some() {
+            if self.starts_with(&closing) {
+                let body_end = self.position.index;
+                for _ in 1..closing.len() {
+                    self.consume();
+                }
+                let end_pos = self.position.clone();
+                self.consume();
+                return Ok(Some((
+                    Token::Str(StrInfo {
+                        raw: &self.src_code[start_pos.index..self.position.index],
+                        value: String::from(&self.src_code[body_start..body_end]),
+                        is_raw: true,
+                    }),
+                    PositionRange {
+                        start: start_pos,
+                        end: end_pos,
+                    },
+                )));
+            }
+            self.consume();
+        }
+        self.position = body_start_pos;
+        self.skip_unterminated_literal();
         Err(LexerError {
-            description: format!("A string literal must be closed with '\"'."),
+            code: UNTERMINATED_TOKEN,
+            description: format!("A raw string literal must be closed with '{}'.", closing),
             position_range: PositionRange {
                 start: start_pos.clone(),
                 end: start_pos,
@@ -113,26 +331,40 @@ impl<'a> Lexer<'a> {
         &mut self,
         start_pos: Position,
     ) -> Result<Option<(Token<'a>, PositionRange)>, LexerError> {
-        let first_char = self.src_code.chars().nth(start_pos.index);
-        if first_char.is_none() || first_char.unwrap() != '\'' {
+        if !self.src_code[start_pos.index..].starts_with('\'') {
             return Ok(None);
         }
-        if let Some(range) = self.read_until(
-            |last_char, current| {
-                current == '\'' && (last_char.is_none() || last_char.unwrap() != '\\')
-            },
-            false,
-        ) {
-            return Ok(Some((
-                Token::Char(&self.src_code[start_pos.index..range.end.index + 1]),
-                PositionRange {
-                    start: start_pos,
-                    end: range.end,
-                },
-            )));
+        if let Some(range) = self.read_quoted('\'') {
+            let value = Self::decode_escapes(
+                &self.src_code[range.start.index..range.end.index],
+                range.start,
+            )?;
+            let mut chars = value.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(value), None) => Ok(Some((
+                    Token::Char(CharInfo {
+                        raw: &self.src_code[start_pos.index..self.position.index],
+                        value,
+                    }),
+                    PositionRange {
+                        start: start_pos,
+                        end: range.end,
+                    },
+                ))),
+                _ => Err(LexerError {
+                    code: INVALID_CHAR_LITERAL,
+                    description: String::from("A char literal must contain exactly one character."),
+                    position_range: PositionRange {
+                        start: start_pos,
+                        end: range.end,
+                    },
+                }),
+            };
         }
+        self.skip_unterminated_literal();
         Err(LexerError {
-            description: format!("A char literal must be closed with '\''."),
+            code: UNTERMINATED_TOKEN,
+            description: String::from("A char literal must be closed with '\''."),
             position_range: PositionRange {
                 start: start_pos.clone(),
                 end: start_pos,
@@ -140,204 +372,361 @@ impl<'a> Lexer<'a> {
         })
     }
 
-    fn scan_number(&mut self, whole_number: bool) -> Option<PositionRange> {
-        let pos_before = self.position.clone();
-        let mut last_pos = self.position.clone();
-        let mut end_idx = None;
-        while let Some(current) = self.peek() {
-            if find_reserved_token(&String::from(current)).is_some() || current.is_whitespace() {
-                end_idx = Some(self.position.index);
+    fn is_number_end(c: char) -> bool {
+        find_reserved_token(c.encode_utf8(&mut [0; 4])).is_some() || c.is_whitespace()
+    }
+
+    fn scan_digits(&mut self, last_pos: &mut Position) -> &'a str {
+        let start_idx = self.position.index;
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_digit() && c != '_' {
                 break;
-            } else if current == '.' {
-                if !whole_number {
-                    end_idx = Some(self.position.index);
-                }
+            }
+            *last_pos = self.position.clone();
+            self.consume();
+        }
+        &self.src_code[start_idx..self.position.index]
+    }
+
+    fn scan_suffix(&mut self, last_pos: &mut Position) -> Option<&'a str> {
+        let start_idx = self.position.index;
+        while let Some(c) = self.peek() {
+            if Self::is_number_end(c) {
                 break;
-            } else if current == 'f' {
-                if whole_number {
-                    break;
-                }
+            }
+            *last_pos = self.position.clone();
+            self.consume();
+        }
+        match start_idx == self.position.index {
+            true => None,
+            _ => Some(&self.src_code[start_idx..self.position.index]),
+        }
+    }
+
+    fn scan_number(&mut self) -> Result<Option<(Token<'a>, PositionRange)>, LexerError> {
+        if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+        let start_pos = self.position.clone();
+        let mut last_pos = self.position.clone();
+        let whole_number = self.scan_digits(&mut last_pos);
+        let mut fractional_part = None;
+        if self.peek() == Some('.') {
+            last_pos = self.position.clone();
+            self.consume();
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(LexerError {
+                    code: INVALID_NUMBER,
+                    description: String::from("Number literal is invalid."),
+                    position_range: PositionRange {
+                        start: start_pos,
+                        end: last_pos,
+                    },
+                });
+            }
+            fractional_part = Some(self.scan_digits(&mut last_pos));
+        }
+        let mut exponent = None;
+        if let Some('e') | Some('E') = self.peek() {
+            last_pos = self.position.clone();
+            self.consume();
+            let exponent_start = self.position.index;
+            if let Some('+') | Some('-') = self.peek() {
                 last_pos = self.position.clone();
                 self.consume();
-                match self.peek() {
-                    Some(c) => {
-                        if find_reserved_token(&String::from(c)).is_some() || c.is_whitespace() {
-                            end_idx = Some(self.position.index);
-                        }
-                    }
-                    _ => end_idx = Some(self.position.index),
-                }
-                break;
-            } else if !current.is_digit(10) {
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(LexerError {
+                    code: INVALID_NUMBER,
+                    description: String::from("An exponent must contain at least one digit."),
+                    position_range: PositionRange {
+                        start: start_pos,
+                        end: last_pos,
+                    },
+                });
+            }
+            self.scan_digits(&mut last_pos);
+            exponent = Some(&self.src_code[exponent_start..self.position.index]);
+        }
+        let suffix = self.scan_suffix(&mut last_pos);
+        let range = PositionRange {
+            start: start_pos,
+            end: last_pos,
+        };
+        if let Some(suffix) = suffix {
+            let is_float = fractional_part.is_some() || exponent.is_some();
+            if !FLOAT_SUFFIXES.contains(&suffix)
+                && (is_float || !INTEGER_SUFFIXES.contains(&suffix))
+            {
+                return Err(LexerError {
+                    code: INVALID_NUMBER,
+                    description: format!("Invalid suffix '{}' for a number literal.", suffix),
+                    position_range: range,
+                });
+            }
+        }
+        Ok(Some((
+            Token::Number(NumberInfo {
+                whole_number,
+                fractional_part,
+                exponent,
+                suffix,
+                radix: 10,
+            }),
+            range,
+        )))
+    }
+
+    fn scan_radix_number(&mut self) -> Result<Option<(Token<'a>, PositionRange)>, LexerError> {
+        let radix = if self.starts_with("0x") || self.starts_with("0X") {
+            16
+        } else if self.starts_with("0o") || self.starts_with("0O") {
+            8
+        } else if self.starts_with("0b") || self.starts_with("0B") {
+            2
+        } else {
+            return Ok(None);
+        };
+        let start_pos = self.position.clone();
+        self.consume();
+        let mut last_pos = self.position.clone();
+        self.consume();
+        let digits_start = self.position.index;
+        let mut has_digit = false;
+        let mut invalid_digit = None;
+        while let Some(current) = self.peek() {
+            if Self::is_number_end(current) || current == 'u' || current == 'i' {
                 break;
+            } else if current.is_digit(radix) {
+                has_digit = true;
+            } else if current != '_' && invalid_digit.is_none() {
+                invalid_digit = Some(current);
             }
             last_pos = self.position.clone();
             self.consume();
         }
-        if end_idx.is_some() {
-            return Some(PositionRange {
-                start: pos_before,
-                end: last_pos,
+        let digits_end = self.position.index;
+        let suffix = self.scan_suffix(&mut last_pos);
+        let range = PositionRange {
+            start: start_pos,
+            end: last_pos,
+        };
+        if let Some(digit) = invalid_digit {
+            return Err(LexerError {
+                code: INVALID_NUMBER,
+                description: format!("Invalid digit '{}' in a base-{} literal.", digit, radix),
+                position_range: range,
             });
         }
-        self.position = pos_before;
-        None
+        if !has_digit {
+            return Err(LexerError {
+                code: INVALID_NUMBER,
+                description: format!("A base-{} literal must contain at least one digit.", radix),
+                position_range: range,
+            });
+        }
+        if let Some(suffix) = suffix {
+            if !INTEGER_SUFFIXES.contains(&suffix) {
+                return Err(LexerError {
+                    code: INVALID_NUMBER,
+                    description: format!("Invalid suffix '{}' for a number literal.", suffix),
+                    position_range: range,
+                });
+            }
+        }
+        Ok(Some((
+            Token::Number(NumberInfo {
+                whole_number: &self.src_code[digits_start..digits_end],
+                fractional_part: None,
+                exponent: None,
+                suffix,
+                radix,
+            }),
+            range,
+        )))
     }
 
-    fn handle_result(&mut self, result: &Result<Option<(Token<'a>, PositionRange)>, LexerError>) {
-        if result.is_ok() {
-            return;
-        }
+    fn starts_with(&self, pattern: &str) -> bool {
+        self.remaining().starts_with(pattern)
+    }
+
+    fn skip_to_line_end(&mut self) {
         while let Some(c) = self.peek() {
-            match c {
-                ';' => break,
-                _ => self.consume(),
+            if c == '\n' {
+                break;
             }
+            self.consume();
         }
     }
 
-    pub fn next_token(&mut self) -> Result<Option<(Token<'a>, PositionRange)>, LexerError> {
+    // Skips the rest of an unterminated literal's line, leaving any trailing `;`, `}` or `)`
+    // to close the statement the literal appeared in.
+    fn skip_unterminated_literal(&mut self) {
+        let line = self.remaining().split('\n').next().unwrap_or("");
+        let skipped =
+            line.trim_end_matches(|c: char| c.is_whitespace() || matches!(c, ';' | '}' | ')'));
+        let end = self.position.index + skipped.len();
+        while self.position.index < end {
+            self.consume();
+        }
+    }
+
+    fn scan_doc_comment(&mut self) -> (Token<'a>, PositionRange) {
+        let start_pos = self.position.clone();
+        let mut last_pos = self.position.clone();
         while let Some(c) = self.peek() {
-            match (c.is_whitespace() || c == '\r') && c != '\n' {
-                true => self.consume(),
-                _ => break,
+            if c == '\n' {
+                break;
             }
+            last_pos = self.position.clone();
+            self.consume();
         }
+        (
+            Token::DocComment(&self.src_code[start_pos.index + 3..self.position.index]),
+            PositionRange {
+                start: start_pos,
+                end: last_pos,
+            },
+        )
+    }
+
+    fn skip_block_comment(&mut self) -> Result<(), LexerError> {
         let start_pos = self.position.clone();
-        let mut last_pos: Option<Position> = None;
+        let mut depth = 0;
         while self.peek().is_some() {
-            if find_reserved_token(&self.src_code[start_pos.index..self.position.index + 1])
-                .is_none()
-            {
-                break;
-            } else {
-                last_pos = Some(self.position.clone());
+            if self.starts_with("/*") {
+                depth += 1;
                 self.consume();
+            } else if self.starts_with("*/") {
+                depth -= 1;
+                self.consume();
+                if depth == 0 {
+                    self.consume();
+                    return Ok(());
+                }
             }
+            self.consume();
         }
-        if let Some(last) = last_pos {
+        Err(LexerError {
+            code: UNTERMINATED_TOKEN,
+            description: String::from("A block comment must be closed with '*/'."),
+            position_range: PositionRange {
+                start: start_pos.clone(),
+                end: start_pos,
+            },
+        })
+    }
+
+    pub fn next_token(&mut self) -> Result<Option<(Token<'a>, PositionRange)>, LexerError> {
+        loop {
+            while let Some(c) = self.peek() {
+                match (c.is_whitespace() || c == '\r') && c != '\n' {
+                    true => self.consume(),
+                    _ => break,
+                }
+            }
+            if self.starts_with("///") && !self.starts_with("////") {
+                return Ok(Some(self.scan_doc_comment()));
+            } else if self.starts_with("//") {
+                self.skip_to_line_end();
+            } else if self.starts_with("/*") {
+                self.skip_block_comment()?;
+            } else {
+                break;
+            }
+        }
+        match self.scan_raw_str() {
+            Ok(None) => {}
+            result => return result,
+        }
+        let start_pos = self.position.clone();
+        let mut last_pos: Option<Position> = None;
+        if let Some((token, len)) = match_reserved_symbol(self.remaining()) {
+            let mut last = self.position.clone();
+            while self.position.index < start_pos.index + len {
+                last = self.position.clone();
+                self.consume();
+            }
             let result = self.scan_str(start_pos.clone());
-            self.handle_result(&result);
             match result {
                 Ok(None) => {}
                 _ => return result,
             }
             let result = self.scan_char(start_pos.clone());
-            self.handle_result(&result);
             match result {
                 Ok(None) => {}
                 _ => return result,
             }
             return Ok(Some((
-                Token::Reserved(
-                    find_reserved_token(&self.src_code[start_pos.index..self.position.index])
-                        .unwrap(),
-                ),
+                Token::Reserved(token),
                 PositionRange {
                     start: start_pos,
                     end: last,
                 },
             )));
         }
-        match self.scan_number(true) {
-            Some(whole_range) => match self.peek() {
-                Some('.') => {
-                    self.consume();
-                    match self.scan_number(false) {
-                        Some(fractional_range) => {
-                            return Ok(Some((
-                                Token::Number(NumberInfo {
-                                    whole_number: &self.src_code
-                                        [whole_range.start.index..whole_range.end.index + 1],
-                                    fractional_part: Some(
-                                        &self.src_code[fractional_range.start.index
-                                            ..fractional_range.end.index + 1],
-                                    ),
-                                }),
-                                PositionRange {
-                                    start: whole_range.start,
-                                    end: fractional_range.end,
-                                },
-                            )));
-                        }
-                        _ => {
-                            return Err(LexerError {
-                                description: format!("Number literal is invalid."),
-                                position_range: PositionRange {
-                                    start: start_pos,
-                                    end: self.position.clone(),
-                                },
-                            });
-                        }
-                    }
-                }
-                _ => {
-                    return Ok(Some((
-                        Token::Number(NumberInfo {
-                            whole_number: &self.src_code
-                                [whole_range.start.index..whole_range.end.index + 1],
-                            fractional_part: None,
-                        }),
-                        whole_range,
-                    )));
-                }
-            },
-            _ => {}
+        match self.scan_radix_number() {
+            Ok(None) => {}
+            result => return result,
+        }
+        match self.scan_number() {
+            Ok(None) => {}
+            result => return result,
+        }
+        let first_char = match self.peek() {
+            Some(c) => c,
+            None => return Ok(None),
+        };
+        if first_char != '_' && !first_char.is_xid_start() {
+            self.consume();
+            return Err(LexerError {
+                code: INVALID_CHARACTER,
+                description: format!("Invalid character '{}' found.", first_char),
+                position_range: PositionRange {
+                    start: start_pos.clone(),
+                    end: start_pos,
+                },
+            });
         }
         while let Some(c) = self.peek() {
-            if find_reserved_token(&String::from(c)).is_some() || c.is_whitespace() {
+            if !c.is_xid_continue() {
                 break;
-            } else if c == '\r' {
-                self.consume();
-            } else {
-                last_pos = Some(self.position.clone());
-                self.consume();
             }
+            last_pos = Some(self.position.clone());
+            self.consume();
         }
         if let Some(last) = last_pos {
-            if let Some(token) =
-                find_reserved_token(&self.src_code[start_pos.index..self.position.index])
-            {
-                return Ok(Some((
-                    Token::Reserved(token),
-                    PositionRange {
-                        start: start_pos,
-                        end: last,
-                    },
-                )));
-            }
-            if self
-                .src_code
-                .chars()
-                .nth(start_pos.index)
-                .unwrap()
-                .is_digit(10)
-            {
-                return Err(LexerError {
-                    description: format!("Invalid identifier found."),
-                    position_range: PositionRange {
-                        start: start_pos,
-                        end: last,
-                    },
-                });
-            }
-            return Ok(Some((
-                Token::Identifier(&self.src_code[start_pos.index..self.position.index]),
-                PositionRange {
-                    start: start_pos,
-                    end: last,
-                },
-            )));
+            let name = &self.src_code[start_pos.index..self.position.index];
+            let range = PositionRange {
+                start: start_pos,
+                end: last,
+            };
+            return match find_reserved_token(name) {
+                Some(token) => Ok(Some((Token::Reserved(token), range))),
+                None => Ok(Some((Token::Identifier(name), range))),
+            };
         }
         Ok(None)
     }
 }
 
+impl<'a> Iterator for Lexer<'a> {
+    type Item = Result<(Token<'a>, PositionRange), LexerError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_token().transpose()
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::common::position::{Position, PositionRange};
+    use crate::common::{
+        position::{Position, PositionRange},
+        token::Token,
+    };
 
     use super::{Lexer, LexerError};
+    use crate::common::diagnostic::INVALID_CHARACTER;
 
     #[test]
     fn test_lexer_error_to_string() {
@@ -356,11 +745,12 @@ mod tests {
         };
         assert_eq!(
             LexerError {
+                code: INVALID_CHARACTER,
                 description: String::from(description),
                 position_range: range.clone(),
             }
             .to_string(),
-            format!("{} (Position: {})", description, range.to_string())
+            format!("{} (Position: {})", description, range)
         );
     }
 
@@ -412,4 +802,458 @@ mod tests {
         assert_eq!(result.start.index, start_pos.index);
         assert_eq!(result.end.index, end_pos.index);
     }
+
+    fn collect_tokens(src_code: &str) -> Vec<Result<String, String>> {
+        Lexer::new(src_code)
+            .map(|result| match result {
+                Ok((token, _)) => Ok(token.to_string()),
+                Err(err) => Err(err.to_string()),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_lexer_comments() {
+        let program = "a // line comment\n/* block /* nested */ still comment */ b\n/// Doc text\n//// not doc";
+        assert_eq!(
+            collect_tokens(program),
+            vec![
+                Ok(String::from("(Identifier, Name: a)")),
+                Ok(String::from("(Reserved Char, Value: '\\n')")),
+                Ok(String::from("(Identifier, Name: b)")),
+                Ok(String::from("(Reserved Char, Value: '\\n')")),
+                Ok(String::from("(Doc Comment, Value:  Doc text)")),
+                Ok(String::from("(Reserved Char, Value: '\\n')")),
+            ]
+        );
+        assert_eq!(
+            collect_tokens("x / y /* open /* nested */"),
+            vec![
+                Ok(String::from("(Identifier, Name: x)")),
+                Ok(String::from("(Operator, Value: '/')")),
+                Ok(String::from("(Identifier, Name: y)")),
+                Err(format!(
+                    "A block comment must be closed with '*/'. (Position: {})",
+                    PositionRange {
+                        start: Position {
+                            line: 1,
+                            column: 7,
+                            index: 6,
+                        },
+                        end: Position {
+                            line: 1,
+                            column: 7,
+                            index: 6,
+                        },
+                    }
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_radix_numbers() {
+        assert_eq!(
+            collect_tokens("0xFF_ff 0o17 0B1010 1_000_000 3.141_5"),
+            vec![
+                Ok(String::from("(Number, whole number: FF_ff, radix: 16)")),
+                Ok(String::from("(Number, whole number: 17, radix: 8)")),
+                Ok(String::from("(Number, whole number: 1010, radix: 2)")),
+                Ok(String::from("(Number, whole number: 1_000_000)")),
+                Ok(String::from(
+                    "(Number, whole number: 3, fractional part: 141_5)"
+                )),
+            ]
+        );
+        let range = |start_idx: usize, end_idx: usize| PositionRange {
+            start: Position {
+                line: 1,
+                column: start_idx + 1,
+                index: start_idx,
+            },
+            end: Position {
+                line: 1,
+                column: end_idx + 1,
+                index: end_idx,
+            },
+        };
+        assert_eq!(
+            collect_tokens("0b102 0x;"),
+            vec![
+                Err(format!(
+                    "Invalid digit '2' in a base-2 literal. (Position: {})",
+                    range(0, 4)
+                )),
+                Err(format!(
+                    "A base-16 literal must contain at least one digit. (Position: {})",
+                    range(6, 7)
+                )),
+                Ok(String::from("(Reserved Char, Value: ';')")),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_number_suffixes_and_exponents() {
+        assert_eq!(
+            collect_tokens("1.5e-3 2E10f64 255u8 7i64 1.5f 3f32 0xFFu16 0b1i8"),
+            vec![
+                Ok(String::from(
+                    "(Number, whole number: 1, fractional part: 5, exponent: -3)"
+                )),
+                Ok(String::from(
+                    "(Number, whole number: 2, exponent: 10, suffix: f64)"
+                )),
+                Ok(String::from("(Number, whole number: 255, suffix: u8)")),
+                Ok(String::from("(Number, whole number: 7, suffix: i64)")),
+                Ok(String::from(
+                    "(Number, whole number: 1, fractional part: 5, suffix: f)"
+                )),
+                Ok(String::from("(Number, whole number: 3, suffix: f32)")),
+                Ok(String::from(
+                    "(Number, whole number: FF, radix: 16, suffix: u16)"
+                )),
+                Ok(String::from(
+                    "(Number, whole number: 1, radix: 2, suffix: i8)"
+                )),
+            ]
+        );
+        let range = |start_idx: usize, end_idx: usize| PositionRange {
+            start: Position {
+                line: 1,
+                column: start_idx + 1,
+                index: start_idx,
+            },
+            end: Position {
+                line: 1,
+                column: end_idx + 1,
+                index: end_idx,
+            },
+        };
+        assert_eq!(
+            collect_tokens("1.5u8 1e+ 12abc"),
+            vec![
+                Err(format!(
+                    "Invalid suffix 'u8' for a number literal. (Position: {})",
+                    range(0, 4)
+                )),
+                Err(format!(
+                    "An exponent must contain at least one digit. (Position: {})",
+                    range(6, 8)
+                )),
+                Err(format!(
+                    "Invalid suffix 'abc' for a number literal. (Position: {})",
+                    range(10, 14)
+                )),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_escape_sequences() {
+        let mut lexer = Lexer::new("\"a\\n\\t\\x41\\u{1F600}\\0\\\\\\\"\" '\\'' '\\\\'");
+        match lexer.next_token() {
+            Ok(Some((Token::Str(info), _))) => {
+                assert_eq!(info.raw, "\"a\\n\\t\\x41\\u{1F600}\\0\\\\\\\"\"");
+                assert_eq!(info.value, "a\n\tA\u{1F600}\0\\\"");
+            }
+            _ => panic!("Expected a string literal."),
+        }
+        match lexer.next_token() {
+            Ok(Some((Token::Char(info), _))) => assert_eq!(info.value, '\''),
+            _ => panic!("Expected a char literal."),
+        }
+        match lexer.next_token() {
+            Ok(Some((Token::Char(info), _))) => assert_eq!(info.value, '\\'),
+            _ => panic!("Expected a char literal."),
+        }
+    }
+
+    #[test]
+    fn test_lexer_invalid_escape_sequences() {
+        let range =
+            |line: usize, start_column: usize, start_idx: usize, end_idx: usize| PositionRange {
+                start: Position {
+                    line,
+                    column: start_column,
+                    index: start_idx,
+                },
+                end: Position {
+                    line,
+                    column: start_column + end_idx - start_idx,
+                    index: end_idx,
+                },
+            };
+        assert_eq!(
+            collect_tokens("\"ok\\q\"")[0],
+            Err(format!(
+                "Unknown escape sequence '\\q'. (Position: {})",
+                range(1, 4, 3, 4)
+            ))
+        );
+        assert_eq!(
+            collect_tokens("\n  \"\\x4G\"")[1],
+            Err(format!(
+                "A hex escape must be followed by two hexadecimal digits. (Position: {})",
+                range(2, 4, 4, 7)
+            ))
+        );
+        assert_eq!(
+            collect_tokens("\"\\xFF\"")[0],
+            Err(format!(
+                "A hex escape must be at most '\\x7F'. (Position: {})",
+                range(1, 2, 1, 4)
+            ))
+        );
+        assert_eq!(
+            collect_tokens("\"\\u{110000}\"")[0],
+            Err(format!(
+                "A unicode escape must have the form '\\u{{...}}' with 1 to 6 hexadecimal digits of a valid code point. (Position: {})",
+                range(1, 2, 1, 10)
+            ))
+        );
+        assert_eq!(
+            collect_tokens("'ab'")[0],
+            Err(format!(
+                "A char literal must contain exactly one character. (Position: {})",
+                range(1, 1, 0, 3)
+            ))
+        );
+    }
+
+    #[test]
+    fn test_lexer_raw_and_multi_line_strings() {
+        let program = "r\"C:\\dir\" r#\"SELECT \"id\"\nFROM t;\"# x\n\"a\\\n    b\\nc\" y";
+        let mut lexer = Lexer::new(program);
+        let mut next_str = |expected_raw: &str| match lexer.next_token() {
+            Ok(Some((Token::Str(info), range))) => {
+                assert_eq!(info.raw, expected_raw);
+                (info.value, info.is_raw, range)
+            }
+            _ => panic!("Expected a string literal."),
+        };
+        let (value, is_raw, _) = next_str("r\"C:\\dir\"");
+        assert_eq!(value, "C:\\dir");
+        assert!(is_raw);
+        let (value, is_raw, range) = next_str("r#\"SELECT \"id\"\nFROM t;\"#");
+        assert_eq!(value, "SELECT \"id\"\nFROM t;");
+        assert!(is_raw);
+        assert_eq!((range.end.line, range.end.column), (2, 9));
+        let mut lexer = Lexer::new(&program[program.find(" x").unwrap()..]);
+        let tokens: Vec<_> = std::iter::from_fn(|| lexer.next_token().ok().flatten()).collect();
+        assert_eq!(tokens[0].0.to_string(), "(Identifier, Name: x)");
+        match &tokens[2] {
+            (Token::Str(info), range) => {
+                assert_eq!(info.value, "ab\nc");
+                assert!(!info.is_raw);
+                assert_eq!(
+                    (range.start.line, range.end.line, range.end.column),
+                    (2, 3, 9)
+                );
+            }
+            _ => panic!("Expected a string literal."),
+        }
+        assert_eq!(tokens[3].1.start.line, 3);
+        assert_eq!(tokens[3].1.start.column, 11);
+        assert_eq!(
+            collect_tokens("r##\"open\"#")[0],
+            Err(format!(
+                "A raw string literal must be closed with '\"##'. (Position: {})",
+                PositionRange {
+                    start: Position::new(),
+                    end: Position::new(),
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_lexer_byte_offsets() {
+        let mut lexer = Lexer::new("\"héllo\" 'ß' x");
+        let mut tokens = Vec::new();
+        while let Ok(Some(token)) = lexer.next_token() {
+            tokens.push(token);
+        }
+        assert_eq!(
+            tokens[0].0.to_string(),
+            "(String Literal, Value: \"héllo\")"
+        );
+        assert_eq!(tokens[0].1.end.index, 7);
+        assert_eq!(tokens[1].0.to_string(), "(Char Literal, Value: 'ß')");
+        assert_eq!(tokens[1].1.start.index, 9);
+        assert_eq!(tokens[2].0.to_string(), "(Identifier, Name: x)");
+        assert_eq!(
+            (tokens[2].1.start.column, tokens[2].1.start.index),
+            (13, 14)
+        );
+        let program = "i32 value = 0x1F + 2;\n".repeat(3);
+        let mut lexer = Lexer::new(&program);
+        let mut last = None;
+        let mut count = 0;
+        while let Ok(Some(token)) = lexer.next_token() {
+            last = Some(token);
+            count += 1;
+        }
+        assert_eq!(count, 3 * 8);
+        let (token, range) = last.unwrap();
+        assert_eq!(token.to_string(), "(Reserved Char, Value: '\\n')");
+        assert_eq!((range.start.line, range.start.index), (3, 65));
+    }
+
+    #[test]
+    fn test_lexer_unicode_identifiers() {
+        let program = "café = \"日本語\"; // コメント\n/* ünïcödé */ _变量1 e\u{301} 😀";
+        assert_eq!(
+            collect_tokens(program),
+            vec![
+                Ok(String::from("(Identifier, Name: café)")),
+                Ok(String::from("(Operator, Value: '=')")),
+                Ok(String::from("(String Literal, Value: \"日本語\")")),
+                Ok(String::from("(Reserved Char, Value: ';')")),
+                Ok(String::from("(Reserved Char, Value: '\\n')")),
+                Ok(String::from("(Identifier, Name: _变量1)")),
+                Ok(String::from("(Identifier, Name: e\u{301})")),
+                Err(format!(
+                    "Invalid character '😀' found. (Position: {})",
+                    PositionRange {
+                        start: Position {
+                            line: 2,
+                            column: 23,
+                            index: 68,
+                        },
+                        end: Position {
+                            line: 2,
+                            column: 23,
+                            index: 68,
+                        },
+                    }
+                )),
+            ]
+        );
+        let program = "\"😀\" x";
+        let mut lexer = Lexer::new(program);
+        lexer.next_token().ok();
+        match lexer.next_token() {
+            Ok(Some((Token::Identifier("x"), range))) => {
+                assert_eq!(range.start.column, 5);
+                assert_eq!(range.start.utf16_column(program), 6);
+            }
+            _ => panic!("Expected an identifier."),
+        }
+    }
+
+    #[test]
+    fn test_lexer_tokenize_all() {
+        let (tokens, errors) = Lexer::new("a = 0b2;\nb = 1.5;\nc = @;").tokenize_all();
+        let tokens: Vec<String> = tokens.iter().map(|(token, _)| token.to_string()).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                "(Identifier, Name: a)",
+                "(Operator, Value: '=')",
+                "(Error, Text: 0b2)",
+                "(Reserved Char, Value: ';')",
+                "(Reserved Char, Value: '\\n')",
+                "(Identifier, Name: b)",
+                "(Operator, Value: '=')",
+                "(Number, whole number: 1, fractional part: 5)",
+                "(Reserved Char, Value: ';')",
+                "(Reserved Char, Value: '\\n')",
+                "(Identifier, Name: c)",
+                "(Operator, Value: '=')",
+                "(Error, Text: @)",
+                "(Reserved Char, Value: ';')",
+            ]
+        );
+        let errors: Vec<String> = errors.iter().map(|err| err.description.clone()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Invalid digit '2' in a base-2 literal.",
+                "Invalid character '@' found.",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lexer_error_recovery() {
+        assert_eq!(
+            collect_tokens("s = \"bad \\q escape\"; c = 'ab'; d"),
+            vec![
+                Ok(String::from("(Identifier, Name: s)")),
+                Ok(String::from("(Operator, Value: '=')")),
+                Err(format!(
+                    "Unknown escape sequence '\\q'. (Position: {})",
+                    PositionRange {
+                        start: Position {
+                            line: 1,
+                            column: 10,
+                            index: 9,
+                        },
+                        end: Position {
+                            line: 1,
+                            column: 11,
+                            index: 10,
+                        },
+                    }
+                )),
+                Ok(String::from("(Reserved Char, Value: ';')")),
+                Ok(String::from("(Identifier, Name: c)")),
+                Ok(String::from("(Operator, Value: '=')")),
+                Err(format!(
+                    "A char literal must contain exactly one character. (Position: {})",
+                    PositionRange {
+                        start: Position {
+                            line: 1,
+                            column: 26,
+                            index: 25,
+                        },
+                        end: Position {
+                            line: 1,
+                            column: 29,
+                            index: 28,
+                        },
+                    }
+                )),
+                Ok(String::from("(Reserved Char, Value: ';')")),
+                Ok(String::from("(Identifier, Name: d)")),
+            ]
+        );
+        let (tokens, errors) = Lexer::new("a = \"open;\nb = 1;").tokenize_all();
+        let tokens: Vec<String> = tokens.iter().map(|(token, _)| token.to_string()).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                "(Identifier, Name: a)",
+                "(Operator, Value: '=')",
+                "(Error, Text: \"open)",
+                "(Reserved Char, Value: ';')",
+                "(Reserved Char, Value: '\\n')",
+                "(Identifier, Name: b)",
+                "(Operator, Value: '=')",
+                "(Number, whole number: 1)",
+                "(Reserved Char, Value: ';')",
+            ]
+        );
+        assert_eq!(
+            errors[0].description,
+            "A string literal must be closed with '\"'."
+        );
+    }
+
+    #[test]
+    fn test_lexer_error_diagnostics() {
+        let src_code = "s = \"tab\\q\";";
+        let (_, errors) = Lexer::new(src_code).tokenize_all();
+        assert_eq!(
+            errors[0].to_diagnostic().render("test.chia", src_code),
+            "error[E0003]: Unknown escape sequence '\\q'.
+ --> test.chia:1:9
+  |
+1 | s = \"tab\\q\";
+  |         ^^
+  = help: valid escapes are \\n, \\t, \\r, \\0, \\\\, \\', \\\", \\xHH and \\u{...}
+"
+        );
+    }
 }
@@ -1,38 +1,210 @@
-use crate::common::{reserved::ReservedToken, token::Token};
+use std::fmt;
 
-use super::ast::node::{ASTNode, FnCall, FnDef, ProgramInfo, TypeInfo, TypeVarPair, VarDef};
+use crate::common::{
+    diagnostic::{
+        Diagnostic, Severity, INTERNAL_COMPILER_ERROR, UNEXPECTED_END_OF_FILE, UNEXPECTED_TOKEN,
+    },
+    position::{Position, PositionRange},
+    reserved::{Associativity, ReservedToken},
+    token::Token,
+};
+
+use super::ast::node::{
+    ASTNode, ControlFlowInfo, ControlFlowType, EnumDef, EnumVariant, FnCall, FnDef, ForLoopInfo,
+    ProgramInfo, StructDef, TypeDef, TypeInfo, TypeVarPair, VarDef,
+};
 
 pub struct ParserError<'a, 'b> {
     description: String,
-    token: Option<&'a Token<'b>>,
+    token: Option<&'a (Token<'b>, PositionRange)>,
+    // Set when the parser itself is at fault rather than the source code.
+    is_internal: bool,
 }
 
 impl<'a, 'b> ParserError<'a, 'b> {
-    pub fn new(description: String, token: Option<&'a Token<'b>>) -> ParserError<'a, 'b> {
-        ParserError { description, token }
+    pub fn new(
+        description: String,
+        token: Option<&'a (Token<'b>, PositionRange)>,
+    ) -> ParserError<'a, 'b> {
+        ParserError {
+            description,
+            token,
+            is_internal: false,
+        }
+    }
+
+    pub fn internal(
+        description: String,
+        token: Option<&'a (Token<'b>, PositionRange)>,
+    ) -> ParserError<'a, 'b> {
+        ParserError {
+            description,
+            token,
+            is_internal: true,
+        }
+    }
+
+    pub fn is_internal(&self) -> bool {
+        self.is_internal
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        if self.is_internal {
+            return Diagnostic::new(
+                Severity::Error,
+                INTERNAL_COMPILER_ERROR,
+                format!("Internal compiler error: {}", self.description),
+                self.position_range().cloned(),
+            )
+            .with_note(String::from(
+                "this is a bug in the compiler, please report it",
+            ));
+        }
+        match self.position_range() {
+            Some(range) => Diagnostic::new(
+                Severity::Error,
+                UNEXPECTED_TOKEN,
+                self.description.clone(),
+                Some(range.clone()),
+            ),
+            None => Diagnostic::new(
+                Severity::Error,
+                UNEXPECTED_END_OF_FILE,
+                self.description.clone(),
+                None,
+            )
+            .with_note(String::from(
+                "the file ended before this construct was complete",
+            )),
+        }
+    }
+
+    // Where the offending token is, or None when the error is at the end of the file.
+    pub fn position_range(&self) -> Option<&'a PositionRange> {
+        self.token.map(|(_, range)| range)
+    }
+}
+
+impl<'a, 'b> fmt::Display for ParserError<'a, 'b> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.token {
+            Some((token, _)) => write!(f, "{} (Token: {})", self.description, token),
+            None => write!(f, "{} (Token: end of file)", self.description),
+        }
+    }
+}
+
+enum PendingOperator<'a, 'b> {
+    Binary(&'a ReservedToken<'b>),
+    Ternary(&'a ReservedToken<'b>, Box<ASTNode<'a, 'b>>),
+}
+
+impl<'a, 'b> PendingOperator<'a, 'b> {
+    fn token(&self) -> &'a ReservedToken<'b> {
+        match self {
+            Self::Binary(op) | Self::Ternary(op, _) => op,
+        }
+    }
+
+    // Returns false when there are not enough operands, which is a bug in `parse_expr`.
+    #[allow(clippy::vec_box)]
+    fn reduce(self, operands: &mut Vec<Box<ASTNode<'a, 'b>>>) -> bool {
+        let (operand2, operand1) = match (operands.pop(), operands.pop()) {
+            (Some(operand2), Some(operand1)) => (operand2, operand1),
+            _ => return false,
+        };
+        let span = operand1.span().join(operand2.span());
+        operands.push(Box::new(match self {
+            Self::Binary(op) => ASTNode::new_binary_operation(op, operand1, operand2, span),
+            Self::Ternary(op, middle) => {
+                ASTNode::new_ternary_operation(op, operand1, middle, operand2, span)
+            }
+        }));
+        true
+    }
+}
+
+enum PrefixOperator<'a, 'b> {
+    Operator(&'a ReservedToken<'b>, &'a PositionRange),
+    // `(T) x`, holding the target type and the span of the parenthesized type.
+    Cast(Box<ASTNode<'a, 'b>>, PositionRange),
+}
+
+impl<'a, 'b> PrefixOperator<'a, 'b> {
+    fn apply(self, operand: Box<ASTNode<'a, 'b>>) -> Box<ASTNode<'a, 'b>> {
+        Box::new(match self {
+            Self::Operator(op, range) => {
+                let span = range.join(operand.span());
+                match op {
+                    ReservedToken::Operator("*", _) => ASTNode::new_dereference(operand, span),
+                    ReservedToken::Operator("&", _) => ASTNode::new_address_of(operand, span),
+                    _ => ASTNode::new_prefix_operation(op, operand, span),
+                }
+            }
+            Self::Cast(type_found, range) => {
+                let span = range.join(operand.span());
+                ASTNode::new_cast(type_found, operand, span)
+            }
+        })
     }
 }
 
+// The initialization, condition and step of a `for` loop, each of which may be left out.
+type ForHeader<'a, 'b> = (
+    Option<Box<ASTNode<'a, 'b>>>,
+    Option<Box<ASTNode<'a, 'b>>>,
+    Option<Box<ASTNode<'a, 'b>>>,
+);
+
+// Keywords that begin a new top-level declaration or a new statement, where
+// parsing can resume after an error.
+const TOP_LEVEL_KEYWORDS: [&str; 3] = ["struct", "enum", "typedef"];
+const STATEMENT_KEYWORDS: [&str; 10] = [
+    "if", "while", "do", "for", "switch", "return", "break", "continue", "case", "default",
+];
+// Builtin type names, which are always read as a cast when parenthesized.
+const SCALAR_TYPES: [&str; 10] = [
+    "i8", "i16", "i32", "i64", "u8", "u16", "u32", "u64", "f32", "f64",
+];
+
 pub struct Parser<'a, 'b> {
     program_name: String,
     token_idx: usize,
-    tokens: Vec<&'a Token<'b>>,
+    tokens: Vec<&'a (Token<'b>, PositionRange)>,
+    // Errors recovered from inside blocks, which do not abort the enclosing node.
+    errors: Vec<ParserError<'a, 'b>>,
 }
 
 impl<'a, 'b> Parser<'a, 'b> {
-    pub fn new(program_name: String, tokens: Vec<&'a Token<'b>>) -> Parser<'a, 'b> {
+    pub fn new(program_name: String, tokens: &'a [(Token<'b>, PositionRange)]) -> Parser<'a, 'b> {
+        // Line breaks and doc comments carry no meaning in the grammar.
+        let tokens = tokens
+            .iter()
+            .filter(|(token, _)| {
+                !matches!(
+                    token,
+                    Token::Reserved(ReservedToken::Char('\n')) | Token::DocComment(_)
+                )
+            })
+            .collect();
         Parser {
             program_name,
             token_idx: 0,
             tokens,
+            errors: Vec::new(),
         }
     }
 
     fn peek(&self) -> Option<&'a Token<'b>> {
-        if self.token_idx < self.tokens.len() {
-            return Some(self.tokens[self.token_idx]);
-        }
-        None
+        self.peek_spanned().map(|(token, _)| token)
+    }
+
+    fn peek_spanned(&self) -> Option<&'a (Token<'b>, PositionRange)> {
+        self.tokens.get(self.token_idx).copied()
     }
 
     fn consume(&mut self) {
@@ -41,9 +213,61 @@ impl<'a, 'b> Parser<'a, 'b> {
         }
     }
 
+    // Covers the tokens consumed since `start_idx`.
+    fn span_from(&self, start_idx: usize) -> PositionRange {
+        let start = match self.tokens.get(start_idx) {
+            Some((_, range)) => range.start.clone(),
+            None => match self.tokens.last() {
+                Some((_, range)) => range.end.clone(),
+                None => Position::new(),
+            },
+        };
+        let end = match self.token_idx > start_idx {
+            true => self.tokens[self.token_idx - 1].1.end.clone(),
+            _ => start.clone(),
+        };
+        PositionRange { start, end }
+    }
+
+    // Skips the rest of a broken construct. Stops after a `;` or a closing `}`, or
+    // before a keyword that starts a new declaration (or statement, in a block).
+    fn synchronize(&mut self, in_block: bool) {
+        let mut depth = 0;
+        let mut skipped_any = false;
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Reserved(ReservedToken::Char('{')) => depth += 1,
+                Token::Reserved(ReservedToken::Char('}')) if depth == 0 && in_block => return,
+                Token::Reserved(ReservedToken::Char('}')) if depth <= 1 => {
+                    self.consume();
+                    if !in_block {
+                        self.parse_reserved_char(';');
+                    }
+                    return;
+                }
+                Token::Reserved(ReservedToken::Char('}')) => depth -= 1,
+                Token::Reserved(ReservedToken::Char(';')) if depth == 0 => {
+                    self.consume();
+                    return;
+                }
+                Token::Reserved(ReservedToken::Keyword(keyword))
+                    if depth == 0
+                        && skipped_any
+                        && (TOP_LEVEL_KEYWORDS.contains(keyword)
+                            || (in_block && STATEMENT_KEYWORDS.contains(keyword))) =>
+                {
+                    return
+                }
+                _ => {}
+            }
+            self.consume();
+            skipped_any = true;
+        }
+    }
+
     fn generate_expect_error(
         expected_item: &str,
-        token: Option<&'a Token<'b>>,
+        token: Option<&'a (Token<'b>, PositionRange)>,
     ) -> ParserError<'a, 'b> {
         ParserError::new(format!("Expected {}.", expected_item), token)
     }
@@ -53,14 +277,17 @@ impl<'a, 'b> Parser<'a, 'b> {
         match self.peek() {
             Some(Token::Reserved(ReservedToken::Char('('))) => self.consume(),
             _ => {
-                return Err(Self::generate_expect_error("left paranthesis", self.peek()));
+                return Err(Self::generate_expect_error(
+                    "left paranthesis",
+                    self.peek_spanned(),
+                ));
             }
         }
         let mut inner_types = Vec::new();
         let mut expect_type = true;
         loop {
-            let token = self.peek();
-            match token {
+            let token = self.peek_spanned();
+            match self.peek() {
                 Some(Token::Reserved(ReservedToken::Char(')'))) => {
                     if expect_type && !inner_types.is_empty() {
                         self.token_idx = last_idx;
@@ -95,7 +322,10 @@ impl<'a, 'b> Parser<'a, 'b> {
                 }
             }
         }
-        Ok(Box::new(ASTNode::Tuple(inner_types)))
+        Ok(Box::new(ASTNode::new_tuple(
+            inner_types,
+            self.span_from(last_idx),
+        )))
     }
 
     fn parse_type_qualifiers(&mut self) -> (bool, bool) {
@@ -128,24 +358,44 @@ impl<'a, 'b> Parser<'a, 'b> {
         }
     }
 
+    fn parse_reserved_char(&mut self, c: char) -> Option<&'a Token<'b>> {
+        match self.peek() {
+            Some(token @ Token::Reserved(ReservedToken::Char(token_char))) if *token_char == c => {
+                self.consume();
+                Some(token)
+            }
+            _ => None,
+        }
+    }
+
     fn parse_static(&mut self) -> bool {
         self.parse_keyword("static").is_some()
     }
 
-    fn peek_identifier(&mut self) -> Option<&'a Token<'b>> {
-        match self.peek() {
-            Some(token) => match token {
-                Token::Identifier(_) => Some(token),
-                _ => None,
-            },
+    fn peek_identifier(&mut self) -> Option<&'a (Token<'b>, PositionRange)> {
+        match self.peek_spanned() {
+            Some(spanned @ (Token::Identifier(_), _)) => Some(spanned),
             _ => None,
         }
     }
 
+    fn parse_identifier(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        match self.peek_identifier() {
+            Some((id, range)) => {
+                self.consume();
+                Ok(Box::new(ASTNode::new_identifier(id, range.clone())))
+            }
+            None => Err(Self::generate_expect_error(
+                "identifier",
+                self.peek_spanned(),
+            )),
+        }
+    }
+
     fn parse_expr_parantheses(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
         let last_idx = self.token_idx;
-        let token = self.peek();
-        match token {
+        let token = self.peek_spanned();
+        match self.peek() {
             Some(Token::Reserved(ReservedToken::Char('('))) => self.consume(),
             _ => {
                 self.token_idx = last_idx;
@@ -160,8 +410,8 @@ impl<'a, 'b> Parser<'a, 'b> {
                 return err;
             }
         }
-        let token = self.peek();
-        match token {
+        let token = self.peek_spanned();
+        match self.peek() {
             Some(Token::Reserved(ReservedToken::Char(')'))) => self.consume(),
             _ => {
                 self.token_idx = last_idx;
@@ -171,13 +421,22 @@ impl<'a, 'b> Parser<'a, 'b> {
         inner
     }
 
-    fn parse_prefix_operators(&mut self) -> Vec<&'a ReservedToken<'b>> {
+    fn parse_prefix_operators(&mut self) -> Vec<PrefixOperator<'a, 'b>> {
         let mut prefix_operators = Vec::new();
-        while let Some(token) = self.peek() {
+        while let Some((token, range)) = self.peek_spanned() {
             match token {
+                Token::Reserved(ReservedToken::Char('(')) => match self.parse_cast_prefix() {
+                    Some(cast) => {
+                        prefix_operators.push(cast);
+                        continue;
+                    }
+                    None => break,
+                },
                 Token::Reserved(reserved_token) => match reserved_token {
                     ReservedToken::Operator(_, info) => match info.is_prefix {
-                        true => prefix_operators.push(*reserved_token),
+                        true => {
+                            prefix_operators.push(PrefixOperator::Operator(reserved_token, range))
+                        }
                         _ => break,
                     },
                     _ => break,
@@ -189,19 +448,129 @@ impl<'a, 'b> Parser<'a, 'b> {
         prefix_operators
     }
 
+    // Parses `(T)` as a cast, which must be followed by an operand. A type such as
+    // `(a)`, `(a[i])` or `((a))` could also be a parenthesized expression, and
+    // without a symbol table it is then only read as a type when directly followed
+    // by an operand that cannot continue an expression: `(a) - b` is a subtraction
+    // and `(f)(x)` is a cast, as operators that can also be binary or postfix never
+    // start the operand of such a cast.
+    fn parse_cast_prefix(&mut self) -> Option<PrefixOperator<'a, 'b>> {
+        let last_idx = self.token_idx;
+        // Decided from the tokens alone, as parsing a candidate type may parse whole
+        // expressions in array lengths and would repeat them on every failed attempt.
+        let closing_idx = self.find_closing_parenthesis(last_idx, false)?;
+        // A tuple type, or an identifier followed only by array suffixes, also reads as an
+        // expression.
+        let is_ambiguous = match self.tokens[last_idx + 1] {
+            (Token::Identifier(name), _) => {
+                !SCALAR_TYPES.contains(name)
+                    && (closing_idx == last_idx + 2
+                        || matches!(
+                            self.tokens[closing_idx - 1],
+                            (Token::Reserved(ReservedToken::Operator("]", _)), _)
+                        ))
+            }
+            (Token::Reserved(ReservedToken::Char('(')), _) => true,
+            _ => false,
+        };
+        let is_cast = match self.tokens.get(closing_idx + 1) {
+            Some((Token::Reserved(ReservedToken::Operator(_, info)), _)) if is_ambiguous => {
+                info.is_prefix && !info.is_binary && !info.is_postfix
+            }
+            Some((Token::Reserved(ReservedToken::Char('{')), _)) if is_ambiguous => false,
+            _ => self.starts_operand(closing_idx + 1),
+        };
+        if !is_cast {
+            return None;
+        }
+        self.consume();
+        match self.parse_type() {
+            Ok(type_found) if self.token_idx == closing_idx => {
+                self.consume();
+                Some(PrefixOperator::Cast(type_found, self.span_from(last_idx)))
+            }
+            _ => {
+                self.token_idx = last_idx;
+                None
+            }
+        }
+    }
+
+    // Finds the `)` matching the `(` at `open_idx` within a single statement, giving up at
+    // a brace, or at a `;` unless the parentheses hold a `for` header.
+    fn find_closing_parenthesis(&self, open_idx: usize, allow_semicolons: bool) -> Option<usize> {
+        let mut depth = 0;
+        for (idx, (token, _)) in self.tokens.iter().enumerate().skip(open_idx) {
+            match token {
+                Token::Reserved(ReservedToken::Char('(')) => depth += 1,
+                Token::Reserved(ReservedToken::Char(')')) => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Some(idx);
+                    }
+                }
+                Token::Reserved(ReservedToken::Char(';')) if !allow_semicolons => return None,
+                Token::Reserved(ReservedToken::Char('{' | '}')) => return None,
+                _ => {}
+            }
+        }
+        None
+    }
+
+    // Whether the token at `idx` can begin an operand, including through a prefix operator.
+    fn starts_operand(&self, idx: usize) -> bool {
+        match self.tokens.get(idx) {
+            Some((Token::Reserved(ReservedToken::Operator(_, info)), _)) => info.is_prefix,
+            Some((Token::Reserved(ReservedToken::Char('(' | '{')), _))
+            | Some((Token::Identifier(_), _))
+            | Some((Token::Number(_), _))
+            | Some((Token::Str(_), _))
+            | Some((Token::Char(_), _))
+            | Some((Token::Error(_), _)) => true,
+            _ => false,
+        }
+    }
+
+    // Whether the run of `*` at the current token ends in an operand, making the first one
+    // a multiplication and the rest dereferences, as in `x as T * *p`.
+    fn multiplies_after_stars(&self) -> bool {
+        let mut idx = self.token_idx;
+        while let Some((Token::Reserved(ReservedToken::Operator("*", _)), _)) = self.tokens.get(idx)
+        {
+            idx += 1;
+        }
+        self.starts_operand(idx)
+    }
+
+    // Applies any trailing `as T` casts, which bind tighter than binary operators.
+    // A `*` after the type is only a pointer suffix when no operand follows it,
+    // so `x as T * y` is a multiplication while `x as T*;` casts to a pointer.
+    fn parse_as_casts(
+        &mut self,
+        mut operand: Box<ASTNode<'a, 'b>>,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        while self.parse_keyword("as").is_some() {
+            let type_found = self.parse_type_with(true)?;
+            let span = operand.span().join(type_found.span());
+            operand = Box::new(ASTNode::new_cast(type_found, operand, span));
+        }
+        Ok(operand)
+    }
+
+    #[allow(clippy::vec_box)]
     fn parse_tuple_expr(&mut self) -> Result<Vec<Box<ASTNode<'a, 'b>>>, ParserError<'a, 'b>> {
         let last_idx = self.token_idx;
         match self.peek() {
             Some(Token::Reserved(ReservedToken::Char('('))) => self.consume(),
             _ => {
-                return Err(Self::generate_expect_error("'('", self.peek()));
+                return Err(Self::generate_expect_error("'('", self.peek_spanned()));
             }
         }
         let mut arguments = Vec::new();
         let mut expect_expr = true;
         loop {
-            let token = self.peek();
-            match token {
+            let token = self.peek_spanned();
+            match self.peek() {
                 Some(Token::Reserved(ReservedToken::Char(')'))) => {
                     if expect_expr && !arguments.is_empty() {
                         self.token_idx = last_idx;
@@ -218,8 +587,15 @@ impl<'a, 'b> Parser<'a, 'b> {
                     self.consume();
                     expect_expr = true;
                 }
+                _ if !expect_expr => {
+                    self.token_idx = last_idx;
+                    return Err(Self::generate_expect_error("',' or ')'", token));
+                }
                 _ => match self.parse_expr() {
-                    Ok(arg) => arguments.push(arg),
+                    Ok(arg) => {
+                        arguments.push(arg);
+                        expect_expr = false;
+                    }
                     Err(err) => {
                         self.token_idx = last_idx;
                         return Err(err);
@@ -230,119 +606,247 @@ impl<'a, 'b> Parser<'a, 'b> {
         Ok(arguments)
     }
 
-    fn parse_operand(&mut self) -> Result<Option<Box<ASTNode<'a, 'b>>>, ParserError> {
-        let mut operand = None;
+    fn parse_operand(&mut self) -> Result<Option<Box<ASTNode<'a, 'b>>>, ParserError<'a, 'b>> {
+        let (token, range) = match self.peek_spanned() {
+            Some(spanned) => spanned,
+            None => return Ok(None),
+        };
+        let operand = match token {
+            Token::Reserved(ReservedToken::Char('(')) => {
+                return self.parse_expr_parantheses().map(Some);
+            }
+            Token::Reserved(ReservedToken::Char('{')) => {
+                return self.parse_initializer_list().map(Some);
+            }
+            Token::Identifier(_) => Box::new(ASTNode::new_identifier(token, range.clone())),
+            Token::Number(_) => Box::new(ASTNode::new_number(token, range.clone())),
+            Token::Str(_) => Box::new(ASTNode::new_string(token, range.clone())),
+            Token::Char(_) => Box::new(ASTNode::new_char(token, range.clone())),
+            // The lexer has already reported the error, so the operand stands in for it.
+            Token::Error(_) => Box::new(ASTNode::new_error(token, range.clone())),
+            _ => return Ok(None),
+        };
+        self.consume();
+        Ok(Some(operand))
+    }
+
+    // Parses `{a, b}` or `{.x = a, .y = b}`, allowing a trailing comma.
+    fn parse_initializer_list(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
         let last_idx = self.token_idx;
-        if let Some(token) = self.peek() {
-            operand = match token {
-                Token::Identifier(_) => {
-                    self.consume();
-                    match self.peek() {
-                        Some(Token::Reserved(ReservedToken::Char('('))) => {
-                            match self.parse_tuple_expr() {
-                                Ok(args) => Some(Box::new(ASTNode::new_function_call(
-                                    FnCall::new(Box::new(ASTNode::new_identifier(token)), args),
-                                ))),
-                                Err(err) => {
-                                    self.token_idx = last_idx;
-                                    return Err(err);
-                                }
-                            }
-                        }
-                        _ => Some(Box::new(ASTNode::new_identifier(token))),
-                    }
+        if self.parse_reserved_char('{').is_none() {
+            return Err(Self::generate_expect_error("'{'", self.peek_spanned()));
+        }
+        let mut items = Vec::new();
+        while self.parse_reserved_char('}').is_none() {
+            match self.parse_initializer_item() {
+                Ok(item) => items.push(item),
+                Err(err) => {
+                    self.token_idx = last_idx;
+                    return Err(err);
                 }
-                Token::Number(_) => Some(Box::new(ASTNode::new_number(token))),
-                Token::Str(_) => Some(Box::new(ASTNode::new_string(token))),
-                Token::Char(_) => Some(Box::new(ASTNode::new_char(token))),
-                _ => None,
-            };
+            }
+            if self.parse_reserved_char(',').is_none() {
+                let token = self.peek_spanned();
+                if self.parse_reserved_char('}').is_none() {
+                    self.token_idx = last_idx;
+                    return Err(Self::generate_expect_error("',' or '}'", token));
+                }
+                break;
+            }
         }
-        if operand.is_some() {
+        Ok(Box::new(ASTNode::new_initializer_list(
+            items,
+            self.span_from(last_idx),
+        )))
+    }
+
+    fn parse_initializer_item(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let last_idx = self.token_idx;
+        if let Some(Token::Reserved(ReservedToken::Operator(".", _))) = self.peek() {
+            self.consume();
+            let field = self.parse_identifier()?;
+            if !matches!(
+                self.peek(),
+                Some(Token::Reserved(ReservedToken::Operator("=", _)))
+            ) {
+                return Err(Self::generate_expect_error("'='", self.peek_spanned()));
+            }
             self.consume();
+            let value = self.parse_expr()?;
+            return Ok(Box::new(ASTNode::new_field_initializer(
+                field,
+                value,
+                self.span_from(last_idx),
+            )));
         }
-        Ok(operand)
+        self.parse_expr()
     }
 
-    fn parse_postfix_operators(&mut self) -> Vec<&'a ReservedToken<'b>> {
-        let mut postfix_operators = Vec::new();
-        while let Some(token) = self.peek() {
-            match token {
-                Token::Reserved(reserved_token) => match reserved_token {
-                    ReservedToken::Operator(_, info) => match info.is_postfix {
-                        true => postfix_operators.push(*reserved_token),
-                        _ => break,
-                    },
-                    _ => break,
+    // Applies calls, field accesses, paths, indexing and other postfix operators
+    // to `operand`, from left to right.
+    fn parse_postfix_operations(
+        &mut self,
+        mut operand: Box<ASTNode<'a, 'b>>,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let last_idx = self.token_idx;
+        while let Some((token, range)) = self.peek_spanned() {
+            operand = match token {
+                Token::Reserved(ReservedToken::Char('(')) => match self.parse_tuple_expr() {
+                    Ok(args) => {
+                        let span = operand.span().join(&self.span_from(last_idx));
+                        Box::new(ASTNode::new_function_call(FnCall::new(operand, args), span))
+                    }
+                    Err(err) => {
+                        self.token_idx = last_idx;
+                        return Err(err);
+                    }
                 },
+                Token::Reserved(ReservedToken::Operator(symbol @ ("." | "::"), _)) => {
+                    self.consume();
+                    let name = match self.parse_identifier() {
+                        Ok(name) => name,
+                        Err(err) => {
+                            self.token_idx = last_idx;
+                            return Err(err);
+                        }
+                    };
+                    let span = operand.span().join(name.span());
+                    Box::new(match *symbol {
+                        "." => ASTNode::new_field_access(operand, name, span),
+                        _ => ASTNode::new_path(operand, name, span),
+                    })
+                }
+                Token::Reserved(ReservedToken::Operator("[", _)) => {
+                    self.consume();
+                    let index = match self.parse_expr() {
+                        Ok(index) => index,
+                        Err(err) => {
+                            self.token_idx = last_idx;
+                            return Err(err);
+                        }
+                    };
+                    let token = self.peek_spanned();
+                    match self.peek() {
+                        Some(Token::Reserved(ReservedToken::Operator("]", _))) => self.consume(),
+                        _ => {
+                            self.token_idx = last_idx;
+                            return Err(Self::generate_expect_error("']'", token));
+                        }
+                    }
+                    let span = operand.span().join(&self.span_from(last_idx));
+                    Box::new(ASTNode::new_index(operand, index, span))
+                }
+                // A closing bracket ends the index expression it belongs to.
+                Token::Reserved(ReservedToken::Operator("]", _)) => break,
+                Token::Reserved(reserved_token @ ReservedToken::Operator(_, info))
+                    if info.is_postfix =>
+                {
+                    self.consume();
+                    let span = operand.span().join(range);
+                    Box::new(ASTNode::new_postfix_operation(
+                        reserved_token,
+                        operand,
+                        span,
+                    ))
+                }
                 _ => break,
-            }
-            self.consume();
+            };
         }
-        postfix_operators
+        Ok(operand)
     }
 
     fn parse_expr(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
         let mut operands = Vec::new();
-        let mut operators: Vec<&'a ReservedToken<'b>> = Vec::new();
+        let mut operators: Vec<PendingOperator<'a, 'b>> = Vec::new();
         let mut operand_expected = true;
         let last_idx = self.token_idx;
         while self.peek().is_some() {
             if operand_expected {
                 let prefix_operators = self.parse_prefix_operators();
-                let operand = self.parse_operand();
-                if let Ok(Some(mut operand_node)) = operand {
-                    let postfix_operators = self.parse_postfix_operators();
-                    for op in postfix_operators.iter() {
-                        operand_node = Box::new(ASTNode::PostfixOperation(*op, operand_node));
+                let operand = match self.parse_operand() {
+                    Ok(Some(operand)) => Some(self.parse_postfix_operations(operand)),
+                    Ok(None) => None,
+                    Err(err) => Some(Err(err)),
+                };
+                if let Some(operand) = operand {
+                    let mut operand_node = match operand {
+                        Ok(operand) => operand,
+                        Err(err) => {
+                            self.token_idx = last_idx;
+                            return Err(err);
+                        }
+                    };
+                    for op in prefix_operators.into_iter().rev() {
+                        operand_node = op.apply(operand_node);
                     }
-                    for op in prefix_operators.iter().rev() {
-                        operand_node = Box::new(ASTNode::PrefixOperation(*op, operand_node));
+                    match self.parse_as_casts(operand_node) {
+                        Ok(operand_node) => operands.push(operand_node),
+                        Err(err) => {
+                            self.token_idx = last_idx;
+                            return Err(err);
+                        }
                     }
-                    operands.push(operand_node);
                 } else {
                     break;
                 }
             } else {
                 let mut succesful = false;
-                if let Some(token) = self.peek() {
-                    match token {
-                        Token::Reserved(reserved_token) => match reserved_token {
-                            ReservedToken::Operator(_, info) => {
-                                if info.is_binary || info.is_ternary {
-                                    succesful = true;
-                                    self.consume();
-                                    while let Some(other_op_token) = operators.pop() {
-                                        match other_op_token {
-                                            ReservedToken::Operator(_, other_info) => {
-                                                if other_info.precedence.unwrap()
-                                                    <= info.precedence.unwrap()
-                                                {
-                                                    let operand2 = operands.pop().unwrap();
-                                                    let operand1 = operands.pop().unwrap();
-                                                    operands.push(Box::new(
-                                                        ASTNode::BinaryOperation(
-                                                            other_op_token,
-                                                            operand1,
-                                                            operand2,
-                                                        ),
-                                                    ));
-                                                } else {
-                                                    operators.push(other_op_token);
-                                                    break;
-                                                }
-                                            }
-                                            _ => {
-                                                panic!("Parser: operators are handled incorrectly.")
-                                            }
-                                        }
-                                    }
-                                    operators.push(reserved_token);
+                if let Some(Token::Reserved(reserved_token @ ReservedToken::Operator(_, info))) =
+                    self.peek()
+                {
+                    if info.is_binary || info.is_ternary {
+                        succesful = true;
+                        self.consume();
+                        while let Some(other_op) = operators.pop() {
+                            let other_precedence = match other_op.token() {
+                                ReservedToken::Operator(_, other_info) => other_info.precedence,
+                                _ => None,
+                            };
+                            let (other_precedence, precedence) = match (
+                                other_precedence,
+                                info.precedence,
+                            ) {
+                                (Some(other), Some(current)) => (other, current),
+                                _ => {
+                                    let token = self.peek_spanned();
+                                    self.token_idx = last_idx;
+                                    return Err(ParserError::internal(
+                                            String::from(
+                                                "An operator without a precedence was treated as binary.",
+                                            ),
+                                            token,
+                                        ));
+                                }
+                            };
+                            if other_precedence < precedence
+                                || (other_precedence == precedence
+                                    && info.associativity == Associativity::Left)
+                            {
+                                if !other_op.reduce(&mut operands) {
+                                    return Err(self.operand_count_error(last_idx));
                                 }
+                            } else {
+                                operators.push(other_op);
+                                break;
                             }
-                            _ => {}
-                        },
-                        _ => {}
+                        }
+                        if info.is_ternary {
+                            let middle = match self.parse_expr() {
+                                Ok(middle) => middle,
+                                Err(err) => {
+                                    self.token_idx = last_idx;
+                                    return Err(err);
+                                }
+                            };
+                            if self.parse_reserved_char(':').is_none() {
+                                let token = self.peek_spanned();
+                                self.token_idx = last_idx;
+                                return Err(Self::generate_expect_error("':'", token));
+                            }
+                            operators.push(PendingOperator::Ternary(reserved_token, middle));
+                        } else {
+                            operators.push(PendingOperator::Binary(reserved_token));
+                        }
                     }
                 }
                 if !succesful {
@@ -352,79 +856,119 @@ impl<'a, 'b> Parser<'a, 'b> {
             operand_expected = !operand_expected;
         }
         if operand_expected {
-            let token = self.peek();
+            let token = self.peek_spanned();
             self.token_idx = last_idx;
             return Err(Self::generate_expect_error("operand", token));
         }
         if operands.len() != operators.len() + 1 {
-            panic!("Parser: the number of operands or operators is not correct.");
+            return Err(self.operand_count_error(last_idx));
         }
         while let Some(op) = operators.pop() {
-            let operand2 = operands.pop().unwrap();
-            let operand1 = operands.pop().unwrap();
-            operands.push(Box::new(ASTNode::BinaryOperation(op, operand1, operand2)));
+            if !op.reduce(&mut operands) {
+                return Err(self.operand_count_error(last_idx));
+            }
+        }
+        match operands.pop() {
+            Some(expr) => Ok(expr),
+            None => Err(self.operand_count_error(last_idx)),
         }
-        Ok(operands.pop().unwrap())
+    }
+
+    fn operand_count_error(&mut self, last_idx: usize) -> ParserError<'a, 'b> {
+        let token = self.peek_spanned();
+        self.token_idx = last_idx;
+        ParserError::internal(
+            String::from("The number of operands or operators is not correct."),
+            token,
+        )
     }
 
     fn parse_type(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        self.parse_type_with(false)
+    }
+
+    // A cast target leaves a `*` that is followed by an operand to the expression.
+    fn parse_type_with(
+        &mut self,
+        is_cast_target: bool,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let start_idx = self.token_idx;
         let is_static = self.parse_static();
         let (is_mut, is_volatile) = self.parse_type_qualifiers();
         let mut base_type = None;
-        if let Some(identifier) = self.peek_identifier() {
+        if let Some((identifier, range)) = self.peek_identifier() {
             self.consume();
-            base_type = Some(Box::new(ASTNode::Type(TypeInfo::new(
-                is_static,
-                is_mut,
-                is_volatile,
-                false,
-                Box::new(ASTNode::Identifier(identifier)),
-            ))));
+            base_type = Some(Box::new(ASTNode::new_type(
+                TypeInfo::new(
+                    is_static,
+                    is_mut,
+                    is_volatile,
+                    false,
+                    Box::new(ASTNode::new_identifier(identifier, range.clone())),
+                ),
+                self.span_from(start_idx),
+            )));
         } else {
-            match self.peek() {
-                Some(Token::Reserved(ReservedToken::Char('('))) => match self.parse_tuple_type() {
+            if let Some(Token::Reserved(ReservedToken::Char('('))) = self.peek() {
+                match self.parse_tuple_type() {
                     Ok(node) => base_type = Some(node),
                     Err(err) => return Err(err),
-                },
-                _ => {}
+                }
             }
         }
         match base_type {
-            None => Err(ParserError {
-                description: format!("Expected an identifier or tuple."),
-                token: self.peek(),
-            }),
+            None => Err(ParserError::new(
+                String::from("Expected an identifier or tuple."),
+                self.peek_spanned(),
+            )),
             Some(_) => {
-                while let Some(token) = self.peek() {
+                while let Some(token) = self.peek_spanned() {
                     match token {
-                        Token::Reserved(ReservedToken::Operator("*", _)) => {
+                        (Token::Reserved(ReservedToken::Operator("*", _)), _)
+                            if !(is_cast_target && self.multiplies_after_stars()) =>
+                        {
                             self.consume();
                             let (is_mut, is_volatile) = self.parse_type_qualifiers();
                             base_type = match base_type {
                                 None => {
-                                    return Err(ParserError {
-                                        description: format!("Invalid pointer notation"),
-                                        token: Some(token),
-                                    })
+                                    return Err(ParserError::new(
+                                        String::from("Invalid pointer notation"),
+                                        Some(token),
+                                    ))
                                 }
-                                Some(base) => Some(Box::new(ASTNode::new_type(TypeInfo::new(
-                                    false,
-                                    is_mut,
-                                    is_volatile,
-                                    true,
-                                    base,
-                                )))),
+                                Some(base) => Some(Box::new(ASTNode::new_type(
+                                    TypeInfo::new(false, is_mut, is_volatile, true, base),
+                                    self.span_from(start_idx),
+                                ))),
                             };
                         }
+                        // Each `[N]` wraps the type so far, so `i32[4][8]` holds 8 `i32[4]`.
+                        (Token::Reserved(ReservedToken::Operator("[", _)), _) => {
+                            self.consume();
+                            let length = self.parse_expr()?;
+                            let token = self.peek_spanned();
+                            match self.peek() {
+                                Some(Token::Reserved(ReservedToken::Operator("]", _))) => {
+                                    self.consume()
+                                }
+                                _ => return Err(Self::generate_expect_error("']'", token)),
+                            }
+                            base_type = base_type.map(|base| {
+                                Box::new(ASTNode::new_type(
+                                    TypeInfo::new_array(length, base),
+                                    self.span_from(start_idx),
+                                ))
+                            });
+                        }
                         _ => break,
                     }
                 }
                 match base_type {
                     Some(base) => Ok(base),
-                    _ => Err(ParserError {
-                        description: format!("Serious internal error occurred when parsing types."),
-                        token: None,
-                    }),
+                    _ => Err(ParserError::internal(
+                        String::from("Serious internal error occurred when parsing types."),
+                        None,
+                    )),
                 }
             }
         }
@@ -439,13 +983,16 @@ impl<'a, 'b> Parser<'a, 'b> {
             Ok(type_found) => {
                 let identifier = self.peek_identifier();
                 match identifier {
-                    None => Err(ParserError {
-                        description: format!("Expected an identifier."),
-                        token: self.peek(),
-                    }),
-                    Some(id) => {
+                    None => Err(ParserError::new(
+                        String::from("Expected an identifier."),
+                        self.peek_spanned(),
+                    )),
+                    Some((id, range)) => {
                         self.consume();
-                        Ok((type_found, Box::new(ASTNode::new_identifier(id))))
+                        Ok((
+                            type_found,
+                            Box::new(ASTNode::new_identifier(id, range.clone())),
+                        ))
                     }
                 }
             }
@@ -458,19 +1005,16 @@ impl<'a, 'b> Parser<'a, 'b> {
         match self.peek() {
             Some(Token::Reserved(ReservedToken::Char('('))) => self.consume(),
             _ => {
-                return Err(ParserError {
-                    description: format!("Expected '('."),
-                    token: self.peek(),
-                })
+                return Err(ParserError::new(
+                    String::from("Expected '('."),
+                    self.peek_spanned(),
+                ))
             }
         }
         while let Some(token) = self.peek() {
-            match token {
-                Token::Reserved(ReservedToken::Char(')')) => {
-                    self.consume();
-                    break;
-                }
-                _ => {}
+            if let Token::Reserved(ReservedToken::Char(')')) = token {
+                self.consume();
+                break;
             }
             match self.parse_type_identifier() {
                 Ok((type_found, id)) => args.push(TypeVarPair::new(type_found, id)),
@@ -479,21 +1023,368 @@ impl<'a, 'b> Parser<'a, 'b> {
                     return Err(err);
                 }
             }
+            let token = self.peek_spanned();
             match self.peek() {
                 Some(Token::Reserved(ReservedToken::Char(','))) => self.consume(),
                 Some(Token::Reserved(ReservedToken::Char(')'))) => {}
-                token => {
+                _ => {
                     self.token_idx = idx_before;
-                    return Err(ParserError {
-                        description: format!("Expected ',' or ')'."),
+                    return Err(ParserError::new(
+                        String::from("Expected ',' or ')'."),
                         token,
-                    });
+                    ));
                 }
             }
         }
         Ok(args)
     }
 
+    fn parse_var_def(
+        &mut self,
+        type_found: Box<ASTNode<'a, 'b>>,
+        id: Box<ASTNode<'a, 'b>>,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let last_idx = self.token_idx;
+        let value = match self.peek() {
+            Some(Token::Reserved(ReservedToken::Operator("=", _))) => {
+                self.consume();
+                match self.parse_expr() {
+                    Ok(expr) => Some(expr),
+                    Err(err) => {
+                        self.token_idx = last_idx;
+                        return Err(err);
+                    }
+                }
+            }
+            Some(Token::Reserved(ReservedToken::Char(';'))) => None,
+            _ => {
+                return Err(ParserError::new(
+                    String::from("Expected ';' or '='."),
+                    self.peek_spanned(),
+                ))
+            }
+        };
+        if self.parse_reserved_char(';').is_none() {
+            let token = self.peek_spanned();
+            self.token_idx = last_idx;
+            return Err(ParserError::new(String::from("Expected ';'."), token));
+        }
+        let span = type_found.span().join(&self.span_from(last_idx));
+        Ok(Box::new(ASTNode::new_variable(
+            VarDef::new(TypeVarPair::new(type_found, id), value),
+            span,
+        )))
+    }
+
+    fn parse_block(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let last_idx = self.token_idx;
+        if self.parse_reserved_char('{').is_none() {
+            return Err(Self::generate_expect_error("'{'", self.peek_spanned()));
+        }
+        let mut statements = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Token::Reserved(ReservedToken::Char('}'))) => {
+                    self.consume();
+                    break;
+                }
+                // The block is left unclosed, so keep what was parsed of it.
+                None => {
+                    self.errors.push(Self::generate_expect_error("'}'", None));
+                    break;
+                }
+                Some(Token::Reserved(ReservedToken::Keyword(keyword)))
+                    if TOP_LEVEL_KEYWORDS.contains(keyword) =>
+                {
+                    self.errors
+                        .push(Self::generate_expect_error("'}'", self.peek_spanned()));
+                    break;
+                }
+                _ => match self.parse_statement() {
+                    Ok(statement) => statements.push(statement),
+                    Err(err) => {
+                        self.errors.push(err);
+                        self.synchronize(true);
+                    }
+                },
+            }
+        }
+        Ok(Box::new(ASTNode::new_sequence(
+            statements,
+            self.span_from(last_idx),
+        )))
+    }
+
+    fn parse_jump_statement(
+        &mut self,
+    ) -> Option<Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>>> {
+        let last_idx = self.token_idx;
+        let keyword = ["break", "continue", "return"]
+            .into_iter()
+            .find(|keyword| self.parse_keyword(keyword).is_some())?;
+        let value = match (keyword, self.peek()) {
+            ("return", Some(Token::Reserved(ReservedToken::Char(';'))))
+            | ("break" | "continue", _) => None,
+            _ => match self.parse_expr() {
+                Ok(expr) => Some(expr),
+                Err(err) => {
+                    self.token_idx = last_idx;
+                    return Some(Err(err));
+                }
+            },
+        };
+        if self.parse_reserved_char(';').is_none() {
+            let token = self.peek_spanned();
+            self.token_idx = last_idx;
+            return Some(Err(Self::generate_expect_error("';'", token)));
+        }
+        let span = self.span_from(last_idx);
+        Some(Ok(Box::new(match keyword {
+            "break" => ASTNode::new_break(span),
+            "continue" => ASTNode::new_continue(span),
+            _ => ASTNode::new_return(value, span),
+        })))
+    }
+
+    fn parse_if(
+        &mut self,
+        control_type: ControlFlowType,
+        start_idx: usize,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let condition = self.parse_expr_parantheses()?;
+        let sequence = self.parse_statement()?;
+        let mut next_flow = None;
+        let else_idx = self.token_idx;
+        if self.parse_keyword("else").is_some() {
+            next_flow = match self.parse_keyword("if") {
+                Some(_) => Some(self.parse_if(ControlFlowType::ElseIf, else_idx)?),
+                None => {
+                    let sequence = self.parse_statement()?;
+                    Some(Box::new(ASTNode::new_control_flow(
+                        ControlFlowInfo::new(ControlFlowType::Else, None, sequence, None),
+                        self.span_from(else_idx),
+                    )))
+                }
+            };
+        }
+        Ok(Box::new(ASTNode::new_control_flow(
+            ControlFlowInfo::new(control_type, Some(condition), sequence, next_flow),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_switch_arms(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let start_idx = self.token_idx;
+        if self.parse_reserved_char('{').is_none() {
+            return Err(Self::generate_expect_error("'{'", self.peek_spanned()));
+        }
+        let mut arms = Vec::new();
+        let mut is_closed = false;
+        while !is_closed && self.parse_reserved_char('}').is_none() {
+            let arm_idx = self.token_idx;
+            let (control_type, condition) = if self.parse_keyword("case").is_some() {
+                (ControlFlowType::SwitchCase, Some(self.parse_expr()?))
+            } else if self.parse_keyword("default").is_some() {
+                (ControlFlowType::SwitchDefault, None)
+            } else {
+                return Err(Self::generate_expect_error(
+                    "'case', 'default' or '}'",
+                    self.peek_spanned(),
+                ));
+            };
+            if self.parse_reserved_char(':').is_none() {
+                return Err(Self::generate_expect_error("':'", self.peek_spanned()));
+            }
+            let statements_idx = self.token_idx;
+            let mut statements = Vec::new();
+            loop {
+                match self.peek() {
+                    Some(Token::Reserved(ReservedToken::Keyword("case")))
+                    | Some(Token::Reserved(ReservedToken::Keyword("default")))
+                    | Some(Token::Reserved(ReservedToken::Char('}'))) => break,
+                    // The switch is left unclosed, so keep what was parsed of it.
+                    None => {
+                        self.errors.push(Self::generate_expect_error("'}'", None));
+                        is_closed = true;
+                        break;
+                    }
+                    _ => match self.parse_statement() {
+                        Ok(statement) => statements.push(statement),
+                        Err(err) => {
+                            self.errors.push(err);
+                            self.synchronize(true);
+                        }
+                    },
+                }
+            }
+            let sequence = Box::new(ASTNode::new_sequence(
+                statements,
+                self.span_from(statements_idx),
+            ));
+            arms.push(Box::new(ASTNode::new_control_flow(
+                ControlFlowInfo::new(control_type, condition, sequence, None),
+                self.span_from(arm_idx),
+            )));
+        }
+        Ok(Box::new(ASTNode::new_sequence(
+            arms,
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_while(
+        &mut self,
+        start_idx: usize,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let condition = self.parse_expr_parantheses()?;
+        let sequence = self.parse_statement()?;
+        Ok(Box::new(ASTNode::new_control_flow(
+            ControlFlowInfo::new(ControlFlowType::While, Some(condition), sequence, None),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_do_while(
+        &mut self,
+        start_idx: usize,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let sequence = self.parse_statement()?;
+        if self.parse_keyword("while").is_none() {
+            return Err(Self::generate_expect_error("'while'", self.peek_spanned()));
+        }
+        let condition = self.parse_expr_parantheses()?;
+        if self.parse_reserved_char(';').is_none() {
+            return Err(Self::generate_expect_error("';'", self.peek_spanned()));
+        }
+        Ok(Box::new(ASTNode::new_control_flow(
+            ControlFlowInfo::new(ControlFlowType::DoWhile, Some(condition), sequence, None),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_switch(
+        &mut self,
+        start_idx: usize,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let condition = self.parse_expr_parantheses()?;
+        let arms = self.parse_switch_arms()?;
+        Ok(Box::new(ASTNode::new_control_flow(
+            ControlFlowInfo::new(ControlFlowType::Switch, Some(condition), arms, None),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_for(&mut self, start_idx: usize) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let header_idx = self.token_idx;
+        let (init, condition, step) = match self.parse_for_header() {
+            Ok(header) => header,
+            // Skip the whole header so that the body is still parsed as part of the loop.
+            Err(err) => match self.find_closing_parenthesis(header_idx, true) {
+                Some(closing_idx) => {
+                    self.errors.push(err);
+                    self.token_idx = closing_idx + 1;
+                    (None, None, None)
+                }
+                None => return Err(err),
+            },
+        };
+        let sequence = self.parse_statement()?;
+        Ok(Box::new(ASTNode::new_for_loop(
+            ForLoopInfo::new(init, condition, step, sequence),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_for_header(&mut self) -> Result<ForHeader<'a, 'b>, ParserError<'a, 'b>> {
+        if self.parse_reserved_char('(').is_none() {
+            return Err(Self::generate_expect_error("'('", self.peek_spanned()));
+        }
+        let init = match self.parse_reserved_char(';') {
+            Some(_) => None,
+            None => Some(self.parse_simple_statement()?),
+        };
+        let condition = match self.peek() {
+            Some(Token::Reserved(ReservedToken::Char(';'))) => None,
+            _ => Some(self.parse_expr()?),
+        };
+        if self.parse_reserved_char(';').is_none() {
+            return Err(Self::generate_expect_error("';'", self.peek_spanned()));
+        }
+        let step = match self.peek() {
+            Some(Token::Reserved(ReservedToken::Char(')'))) => None,
+            _ => Some(self.parse_expr()?),
+        };
+        if self.parse_reserved_char(')').is_none() {
+            return Err(Self::generate_expect_error("')'", self.peek_spanned()));
+        }
+        Ok((init, condition, step))
+    }
+
+    fn parse_control_flow(&mut self) -> Option<Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>>> {
+        let last_idx = self.token_idx;
+        let result = if self.parse_keyword("if").is_some() {
+            self.parse_if(ControlFlowType::If, last_idx)
+        } else if self.parse_keyword("while").is_some() {
+            self.parse_while(last_idx)
+        } else if self.parse_keyword("do").is_some() {
+            self.parse_do_while(last_idx)
+        } else if self.parse_keyword("for").is_some() {
+            self.parse_for(last_idx)
+        } else if self.parse_keyword("switch").is_some() {
+            self.parse_switch(last_idx)
+        } else {
+            return None;
+        };
+        // A failed statement is not rewound, so the enclosing block resynchronizes from
+        // where it failed instead of reparsing nested blocks and repeating their errors.
+        Some(result)
+    }
+
+    fn parse_expr_statement(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let last_idx = self.token_idx;
+        let expr = self.parse_expr()?;
+        if self.parse_reserved_char(';').is_none() {
+            let token = self.peek_spanned();
+            self.token_idx = last_idx;
+            return Err(Self::generate_expect_error("';'", token));
+        }
+        Ok(Box::new(ASTNode::new_expression(
+            expr,
+            self.span_from(last_idx),
+        )))
+    }
+
+    fn parse_statement(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        if let Some(Token::Reserved(ReservedToken::Char('{'))) = self.peek() {
+            return self.parse_block();
+        }
+        if let Some(result) = self.parse_jump_statement() {
+            return result;
+        }
+        if let Some(result) = self.parse_control_flow() {
+            return result;
+        }
+        self.parse_simple_statement()
+    }
+
+    fn parse_simple_statement(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        // A statement such as `a * b;` reads as both a declaration and an
+        // expression, so an expression is tried whenever a declaration fails.
+        let last_idx = self.token_idx;
+        let decl_err = match self.parse_type_identifier() {
+            Ok((type_found, id)) => match self.parse_var_def(type_found, id) {
+                Ok(node) => return Ok(node),
+                Err(err) => Some(err),
+            },
+            Err(_) => None,
+        };
+        self.token_idx = last_idx;
+        match self.parse_expr_statement() {
+            Ok(node) => Ok(node),
+            Err(err) => Err(decl_err.unwrap_or(err)),
+        }
+    }
+
     fn parse_decl(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
         let last_idx = self.token_idx;
         let type_id_result = self.parse_type_identifier();
@@ -503,80 +1394,1471 @@ impl<'a, 'b> Parser<'a, 'b> {
                 Err(err)
             }
             Ok((type_found, id)) => match self.peek() {
-                Some(Token::Reserved(ReservedToken::Char('('))) => match self.parse_arg_list() {
-                    Ok(args) => Ok(Box::new(ASTNode::new_function(FnDef::new(
-                        type_found, id, args, None,
-                    )))),
+                Some(Token::Reserved(ReservedToken::Char('('))) => match self.parse_fn_args() {
+                    Ok(args) => match self.peek() {
+                        Some(Token::Reserved(ReservedToken::Char(';'))) => {
+                            self.consume();
+                            Ok(Box::new(ASTNode::new_function(
+                                FnDef::new(type_found, id, args, None),
+                                self.span_from(last_idx),
+                            )))
+                        }
+                        Some(Token::Reserved(ReservedToken::Char('{'))) => {
+                            match self.parse_block() {
+                                Ok(body) => Ok(Box::new(ASTNode::new_function(
+                                    FnDef::new(type_found, id, args, Some(body)),
+                                    self.span_from(last_idx),
+                                ))),
+                                Err(err) => {
+                                    self.token_idx = last_idx;
+                                    Err(err)
+                                }
+                            }
+                        }
+                        _ => {
+                            let token = self.peek_spanned();
+                            self.token_idx = last_idx;
+                            Err(Self::generate_expect_error("';' or '{'", token))
+                        }
+                    },
                     Err(err) => {
                         self.token_idx = last_idx;
                         Err(err)
                     }
                 },
-                Some(Token::Reserved(ReservedToken::Operator("=", _))) => {
-                    self.consume();
-                    match self.parse_expr() {
-                        Ok(expr) => {
-                            match self.peek() {
-                                Some(Token::Reserved(ReservedToken::Char(';'))) => self.consume(),
-                                _ => {
-                                    let token = self.peek();
-                                    self.token_idx = last_idx;
-                                    return Err(ParserError {
-                                        description: format!("Expected ';'."),
-                                        token,
-                                    });
-                                }
-                            }
-                            Ok(Box::new(ASTNode::new_variable(VarDef::new(
-                                TypeVarPair::new(type_found, id),
-                                Some(expr),
-                            ))))
-                        }
+                Some(Token::Reserved(ReservedToken::Operator("=", _)))
+                | Some(Token::Reserved(ReservedToken::Char(';'))) => {
+                    match self.parse_var_def(type_found, id) {
+                        Ok(node) => Ok(node),
                         Err(err) => {
                             self.token_idx = last_idx;
                             Err(err)
                         }
                     }
                 }
-                Some(Token::Reserved(ReservedToken::Operator(";", _))) => Ok(Box::new(
-                    ASTNode::new_variable(VarDef::new(TypeVarPair::new(type_found, id), None)),
-                )),
                 _ => {
+                    let token = self.peek_spanned();
                     self.token_idx = last_idx;
-                    Err(ParserError {
-                        description: format!("Expected ';', '=' or '('."),
-                        token: self.peek(),
-                    })
+                    Err(ParserError::new(
+                        String::from("Expected ';', '=' or '('."),
+                        token,
+                    ))
                 }
             },
         }
     }
 
-    pub fn parse(&mut self) -> Result<ASTNode<'a, 'b>, Vec<ParserError>> {
+    // Parses the argument list of a function. A malformed list is skipped and left empty,
+    // keeping the function so that its body is still parsed.
+    fn parse_fn_args(&mut self) -> Result<Vec<TypeVarPair<'a, 'b>>, ParserError<'a, 'b>> {
+        let args_idx = self.token_idx;
+        match self.parse_arg_list() {
+            Ok(args) => Ok(args),
+            Err(err) => match self.find_closing_parenthesis(args_idx, false) {
+                Some(closing_idx) => {
+                    self.errors.push(err);
+                    self.token_idx = closing_idx + 1;
+                    Ok(Vec::new())
+                }
+                None => Err(err),
+            },
+        }
+    }
+
+    fn parse_struct_field(&mut self) -> Result<TypeVarPair<'a, 'b>, ParserError<'a, 'b>> {
+        let (type_found, id) = self.parse_type_identifier()?;
+        if self.parse_reserved_char(';').is_none() {
+            return Err(Self::generate_expect_error("';'", self.peek_spanned()));
+        }
+        Ok(TypeVarPair::new(type_found, id))
+    }
+
+    fn parse_struct_def(
+        &mut self,
+        start_idx: usize,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let identifier = self.parse_identifier()?;
+        if self.parse_reserved_char('{').is_none() {
+            return Err(Self::generate_expect_error("'{'", self.peek_spanned()));
+        }
+        let mut fields = Vec::new();
+        while self.parse_reserved_char('}').is_none() {
+            match self.parse_struct_field() {
+                Ok(field) => fields.push(field),
+                Err(err) if self.peek().is_some() => {
+                    self.errors.push(err);
+                    self.synchronize(true);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if self.parse_reserved_char(';').is_none() {
+            return Err(Self::generate_expect_error("';'", self.peek_spanned()));
+        }
+        Ok(Box::new(ASTNode::new_struct_def(
+            StructDef::new(identifier, fields),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_enum_def(
+        &mut self,
+        start_idx: usize,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let identifier = self.parse_identifier()?;
+        if self.parse_reserved_char('{').is_none() {
+            return Err(Self::generate_expect_error("'{'", self.peek_spanned()));
+        }
+        let mut fields = Vec::new();
+        while self.parse_reserved_char('}').is_none() {
+            match self.parse_enum_variant() {
+                Ok(variant) => fields.push(variant),
+                Err(err) if self.peek().is_some() => {
+                    self.errors.push(err);
+                    self.skip_enum_variant();
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        if self.parse_reserved_char(';').is_none() {
+            return Err(Self::generate_expect_error("';'", self.peek_spanned()));
+        }
+        Ok(Box::new(ASTNode::new_enum_def(
+            EnumDef::new(identifier, fields),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_enum_variant(&mut self) -> Result<ASTNode<'a, 'b>, ParserError<'a, 'b>> {
+        let variant_idx = self.token_idx;
+        let variant_id = self.parse_identifier()?;
+        let value = match self.peek() {
+            Some(Token::Reserved(ReservedToken::Operator("=", _))) => {
+                self.consume();
+                Some(self.parse_expr()?)
+            }
+            _ => None,
+        };
+        let variant = ASTNode::new_enum_variant(
+            EnumVariant::new(variant_id, value),
+            self.span_from(variant_idx),
+        );
+        let token = self.peek_spanned();
+        match self.peek() {
+            Some(Token::Reserved(ReservedToken::Char(','))) => self.consume(),
+            Some(Token::Reserved(ReservedToken::Char('}'))) => {}
+            _ => return Err(Self::generate_expect_error("',' or '}'", token)),
+        }
+        Ok(variant)
+    }
+
+    // Skips the rest of a malformed variant, up to and including the next ','.
+    fn skip_enum_variant(&mut self) {
+        while let Some(token) = self.peek() {
+            match token {
+                Token::Reserved(ReservedToken::Char('}')) => return,
+                Token::Reserved(ReservedToken::Char(',')) => {
+                    self.consume();
+                    return;
+                }
+                _ => self.consume(),
+            }
+        }
+    }
+
+    fn parse_type_def(
+        &mut self,
+        start_idx: usize,
+    ) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let definition = self.parse_type()?;
+        let (type_name, _) = match self.peek_identifier() {
+            Some(id) => id,
+            None => {
+                return Err(Self::generate_expect_error(
+                    "identifier",
+                    self.peek_spanned(),
+                ))
+            }
+        };
+        self.consume();
+        if self.parse_reserved_char(';').is_none() {
+            return Err(Self::generate_expect_error("';'", self.peek_spanned()));
+        }
+        Ok(Box::new(ASTNode::new_type_def(
+            TypeDef::new(type_name, definition),
+            self.span_from(start_idx),
+        )))
+    }
+
+    fn parse_top_level_decl(&mut self) -> Result<Box<ASTNode<'a, 'b>>, ParserError<'a, 'b>> {
+        let last_idx = self.token_idx;
+        let result = if self.parse_keyword("struct").is_some() {
+            self.parse_struct_def(last_idx)
+        } else if self.parse_keyword("enum").is_some() {
+            self.parse_enum_def(last_idx)
+        } else if self.parse_keyword("typedef").is_some() {
+            self.parse_type_def(last_idx)
+        } else {
+            return self.parse_decl();
+        };
+        if result.is_err() {
+            self.token_idx = last_idx;
+        }
+        result
+    }
+
+    // Parses as much of the program as possible, returning it along with every error found.
+    pub fn parse_partial(&mut self) -> (ASTNode<'a, 'b>, Vec<ParserError<'a, 'b>>) {
         let mut definitions = Vec::new();
-        let mut errors = Vec::new();
         while self.peek().is_some() {
-            match self.parse_decl() {
+            match self.parse_top_level_decl() {
                 Ok(node) => definitions.push(node),
                 Err(err) => {
-                    errors.push(err);
-                    while let Some(c) = self.peek() {
-                        match c {
-                            Token::Reserved(&ReservedToken::Char(';')) => break,
-                            _ => self.consume(),
-                        }
-                    }
-                    self.consume();
+                    self.errors.push(err);
+                    self.synchronize(false);
                 }
             }
         }
-        if errors.is_empty() {
-            Ok(ASTNode::Program(ProgramInfo::new(
-                self.program_name.clone(),
-                definitions,
-            )))
-        } else {
-            Err(errors)
+        let program = ASTNode::new_program(
+            ProgramInfo::new(self.program_name.clone(), definitions),
+            self.span_from(0),
+        );
+        let mut errors = std::mem::take(&mut self.errors);
+        // Errors recovered inside a node are found before the error that ends it.
+        errors.sort_by_key(|err| match err.position_range() {
+            Some(range) => (false, range.start.index),
+            None => (true, 0),
+        });
+        (program, errors)
+    }
+
+    pub fn parse(&mut self) -> Result<ASTNode<'a, 'b>, Vec<ParserError<'a, 'b>>> {
+        match self.parse_partial() {
+            (program, errors) if errors.is_empty() => Ok(program),
+            (_, errors) => Err(errors),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chia::{ast::node::ASTNodeKind, lexer::Lexer};
+    use crate::common::{
+        reserved::{Associativity, OperatorInfo, ReservedToken},
+        token::Token,
+    };
+
+    use super::Parser;
+
+    fn parse_to_string(src_code: &str) -> Result<String, Vec<String>> {
+        let (tokens, _) = Lexer::new(src_code).tokenize_all();
+        let mut parser = Parser::new(String::from("test"), &tokens);
+        match parser.parse() {
+            Ok(program) => Ok(program.to_string()),
+            Err(errors) => Err(errors.iter().map(|err| err.to_string()).collect()),
+        }
+    }
+
+    #[test]
+    fn test_parse_var_decl() {
+        let result = parse_to_string("static mut i32 x;\nu8* y = (1 + 2) * 3;");
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Variable
+    TypeVarPair
+      Type (static, mut)
+        Identifier: i32
+      Identifier: x
+  Variable
+    TypeVarPair
+      Type (pointer)
+        Type
+          Identifier: u8
+      Identifier: y
+    BinaryOperation: *
+      BinaryOperation: +
+        Number: 1
+        Number: 2
+      Number: 3
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_fn_prototype() {
+        let result = parse_to_string("i32 add(i32 a, i32 b);\nf64 pi();");
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: i32
+    Identifier: add
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: a
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: b
+  Function
+    Type
+      Identifier: f64
+    Identifier: pi
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_fn_call_in_initializer() {
+        let result = parse_to_string("i32 x = max(a, 2) - 1;");
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Variable
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: x
+    BinaryOperation: -
+      FunctionCall
+        Identifier: max
+        Identifier: a
+        Number: 2
+      Number: 1
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let result = parse_to_string("i32 x = ;\ni32 y = 1;\ni32 z");
+        assert_eq!(
+            result,
+            Err(vec![
+                String::from("Expected operand. (Token: (Reserved Char, Value: ';'))"),
+                String::from("Expected ';', '=' or '('. (Token: end of file)"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_fn_body() {
+        let result = parse_to_string(
+            "i32 sum(i32* values, i32 count) {
+    mut i32 total = 0;
+    {
+        total += values * count;
+    }
+    next(total);
+    return total;
+}
+void idle() { break; continue; return; }",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: i32
+    Identifier: sum
+    TypeVarPair
+      Type (pointer)
+        Type
+          Identifier: i32
+      Identifier: values
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: count
+    Sequence
+      Variable
+        TypeVarPair
+          Type (mut)
+            Identifier: i32
+          Identifier: total
+        Number: 0
+      Sequence
+        Expression
+          BinaryOperation: +=
+            Identifier: total
+            BinaryOperation: *
+              Identifier: values
+              Identifier: count
+      Expression
+        FunctionCall
+          Identifier: next
+          Identifier: total
+      Return
+        Identifier: total
+  Function
+    Type
+      Identifier: void
+    Identifier: idle
+    Sequence
+      Break
+      Continue
+      Return
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_fn_body_errors() {
+        assert_eq!(
+            parse_to_string("void f() { return 1 }"),
+            Err(vec![String::from(
+                "Expected ';'. (Token: (Reserved Char, Value: '}'))"
+            )])
+        );
+        assert_eq!(
+            parse_to_string("void f() { i32 x = ; }").unwrap_err()[0],
+            "Expected operand. (Token: (Reserved Char, Value: ';'))"
+        );
+        assert_eq!(
+            parse_to_string("void f() { g();"),
+            Err(vec![String::from("Expected '}'. (Token: end of file)")])
+        );
+    }
+
+    #[test]
+    fn test_parse_if_else() {
+        let result = parse_to_string(
+            "void f() {
+    if (a < b) { g(); } else if (a == b) h(); else { return; }
+    if (c) {}
+}",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      ControlFlow: If
+        BinaryOperation: <
+          Identifier: a
+          Identifier: b
+        Sequence
+          Expression
+            FunctionCall
+              Identifier: g
+        ControlFlow: ElseIf
+          BinaryOperation: ==
+            Identifier: a
+            Identifier: b
+          Expression
+            FunctionCall
+              Identifier: h
+          ControlFlow: Else
+            Sequence
+              Return
+      ControlFlow: If
+        Identifier: c
+        Sequence
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_loops() {
+        let result = parse_to_string(
+            "void f() {
+    while (i < 10) { i += 1; }
+    do { i -= 1; } while (i > 0);
+}",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      ControlFlow: While
+        BinaryOperation: <
+          Identifier: i
+          Number: 10
+        Sequence
+          Expression
+            BinaryOperation: +=
+              Identifier: i
+              Number: 1
+      ControlFlow: DoWhile
+        BinaryOperation: >
+          Identifier: i
+          Number: 0
+        Sequence
+          Expression
+            BinaryOperation: -=
+              Identifier: i
+              Number: 1
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_switch() {
+        let result = parse_to_string(
+            "void f() {
+    switch (x) {
+        case 1:
+        case 2:
+            g();
+            break;
+        default:
+            return;
+    }
+}",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      ControlFlow: Switch
+        Identifier: x
+        Sequence
+          ControlFlow: SwitchCase
+            Number: 1
+            Sequence
+          ControlFlow: SwitchCase
+            Number: 2
+            Sequence
+              Expression
+                FunctionCall
+                  Identifier: g
+              Break
+          ControlFlow: SwitchDefault
+            Sequence
+              Return
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_control_flow_errors() {
+        assert_eq!(
+            parse_to_string("void f() { do {} (x); }").unwrap_err()[0],
+            "Expected 'while'. (Token: (Reserved Char, Value: '('))"
+        );
+        assert_eq!(
+            parse_to_string("void f() { switch (x) { g(); } }").unwrap_err()[0],
+            "Expected 'case', 'default' or '}'. (Token: (Identifier, Name: g))"
+        );
+        assert_eq!(
+            parse_to_string("void f() { while x {} }").unwrap_err()[0],
+            "Expected '('. (Token: (Identifier, Name: x))"
+        );
+    }
+
+    #[test]
+    fn test_parse_for() {
+        let result = parse_to_string(
+            "void f() {
+    for (mut i32 i = 0; i < n; i += 1) { g(i); }
+    for (;;) break;
+}",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      ForLoop
+        Init
+          Variable
+            TypeVarPair
+              Type (mut)
+                Identifier: i32
+              Identifier: i
+            Number: 0
+        Condition
+          BinaryOperation: <
+            Identifier: i
+            Identifier: n
+        Step
+          BinaryOperation: +=
+            Identifier: i
+            Number: 1
+        Sequence
+          Expression
+            FunctionCall
+              Identifier: g
+              Identifier: i
+      ForLoop
+        Break
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_for_errors() {
+        assert_eq!(
+            parse_to_string("void f() { for (i = 0; i < n) {} }").unwrap_err()[0],
+            "Expected ';'. (Token: (Reserved Char, Value: ')'))"
+        );
+        assert_eq!(
+            parse_to_string("void f() { for (;; i += 1 {} }").unwrap_err()[0],
+            "Expected ')'. (Token: (Reserved Char, Value: '{'))"
+        );
+    }
+
+    #[test]
+    fn test_parse_struct_enum_typedef() {
+        let result = parse_to_string(
+            "struct Point {
+    i32 x;
+    mut i32* y;
+};
+enum Color { Red, Green = 5, Blue = 1 << 3, };
+typedef Point* PointPtr;",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  StructDef
+    Identifier: Point
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: x
+    TypeVarPair
+      Type (pointer)
+        Type (mut)
+          Identifier: i32
+      Identifier: y
+  EnumDef
+    Identifier: Color
+    EnumVariant
+      Identifier: Red
+    EnumVariant
+      Identifier: Green
+      Number: 5
+    EnumVariant
+      Identifier: Blue
+      BinaryOperation: <<
+        Number: 1
+        Number: 3
+  TypeDef: PointPtr
+    Type (pointer)
+      Type
+        Identifier: Point
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_struct_enum_typedef_errors() {
+        assert_eq!(
+            parse_to_string("struct { i32 x; };").unwrap_err()[0],
+            "Expected identifier. (Token: (Reserved Char, Value: '{'))"
+        );
+        assert_eq!(
+            parse_to_string("struct S { i32 x };").unwrap_err()[0],
+            "Expected ';'. (Token: (Reserved Char, Value: '}'))"
+        );
+        assert_eq!(
+            parse_to_string("enum E { A B };").unwrap_err()[0],
+            "Expected ',' or '}'. (Token: (Identifier, Name: B))"
+        );
+        assert_eq!(
+            parse_to_string("typedef i32;").unwrap_err()[0],
+            "Expected identifier. (Token: (Reserved Char, Value: ';'))"
+        );
+    }
+
+    #[test]
+    fn test_parse_ternary() {
+        let result = parse_to_string("i32 x = a || b ? c + 1 : d ? e : f;\ni32 y = z = a ? b : c;");
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Variable
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: x
+    TernaryOperation: ?
+      BinaryOperation: ||
+        Identifier: a
+        Identifier: b
+      BinaryOperation: +
+        Identifier: c
+        Number: 1
+      TernaryOperation: ?
+        Identifier: d
+        Identifier: e
+        Identifier: f
+  Variable
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: y
+    BinaryOperation: =
+      Identifier: z
+      TernaryOperation: ?
+        Identifier: a
+        Identifier: b
+        Identifier: c
+"
+            ))
+        );
+        assert_eq!(
+            parse_to_string("i32 x = a ? b;").unwrap_err()[0],
+            "Expected ':'. (Token: (Reserved Char, Value: ';'))"
+        );
+    }
+
+    #[test]
+    fn test_parse_associativity() {
+        let result = parse_to_string("void f() { a = b = c; a += b -= c; a - b - c; }");
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      Expression
+        BinaryOperation: =
+          Identifier: a
+          BinaryOperation: =
+            Identifier: b
+            Identifier: c
+      Expression
+        BinaryOperation: +=
+          Identifier: a
+          BinaryOperation: -=
+            Identifier: b
+            Identifier: c
+      Expression
+        BinaryOperation: -
+          BinaryOperation: -
+            Identifier: a
+            Identifier: b
+          Identifier: c
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_postfix_chains() {
+        let result = parse_to_string(
+            "void f() { obj.method(x)[0]; p.x = -arr[i + 1]++; io::print(s.len()); }",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      Expression
+        Index
+          FunctionCall
+            FieldAccess
+              Identifier: obj
+              Identifier: method
+            Identifier: x
+          Number: 0
+      Expression
+        BinaryOperation: =
+          FieldAccess
+            Identifier: p
+            Identifier: x
+          PrefixOperation: -
+            PostfixOperation: ++
+              Index
+                Identifier: arr
+                BinaryOperation: +
+                  Identifier: i
+                  Number: 1
+      Expression
+        FunctionCall
+          Path
+            Identifier: io
+            Identifier: print
+          FunctionCall
+            FieldAccess
+              Identifier: s
+              Identifier: len
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_postfix_chain_errors() {
+        let result = parse_to_string("void f() { a.; b[1; c::(d); }");
+        assert_eq!(
+            result,
+            Err(vec![
+                String::from("Expected identifier. (Token: (Reserved Char, Value: ';'))"),
+                String::from("Expected ']'. (Token: (Reserved Char, Value: ';'))"),
+                String::from("Expected identifier. (Token: (Reserved Char, Value: '('))"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_pointer_and_cast_exprs() {
+        let result = parse_to_string(
+            "void f() { *p = a * *q & &x; y = (u8*) &buf[1] + (a) - b; z = -n as f64 + (i32) m; }",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      Expression
+        BinaryOperation: =
+          Dereference
+            Identifier: p
+          BinaryOperation: &
+            BinaryOperation: *
+              Identifier: a
+              Dereference
+                Identifier: q
+            AddressOf
+              Identifier: x
+      Expression
+        BinaryOperation: =
+          Identifier: y
+          BinaryOperation: -
+            BinaryOperation: +
+              Cast
+                Type (pointer)
+                  Type
+                    Identifier: u8
+                AddressOf
+                  Index
+                    Identifier: buf
+                    Number: 1
+              Identifier: a
+            Identifier: b
+      Expression
+        BinaryOperation: =
+          Identifier: z
+          BinaryOperation: +
+            Cast
+              Type
+                Identifier: f64
+              PrefixOperation: -
+                Identifier: n
+            Cast
+              Type
+                Identifier: i32
+              Identifier: m
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_cast_disambiguation() {
+        let result = parse_to_string(
+            "void f() { a = len as u64 * 8; b = p as u8**; c = (i32) -x; d = (i32) *p; e = x as i64 * *q; }",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      Expression
+        BinaryOperation: =
+          Identifier: a
+          BinaryOperation: *
+            Cast
+              Type
+                Identifier: u64
+              Identifier: len
+            Number: 8
+      Expression
+        BinaryOperation: =
+          Identifier: b
+          Cast
+            Type (pointer)
+              Type (pointer)
+                Type
+                  Identifier: u8
+            Identifier: p
+      Expression
+        BinaryOperation: =
+          Identifier: c
+          Cast
+            Type
+              Identifier: i32
+            PrefixOperation: -
+              Identifier: x
+      Expression
+        BinaryOperation: =
+          Identifier: d
+          Cast
+            Type
+              Identifier: i32
+            Dereference
+              Identifier: p
+      Expression
+        BinaryOperation: =
+          Identifier: e
+          BinaryOperation: *
+            Cast
+              Type
+                Identifier: i64
+              Identifier: x
+            Dereference
+              Identifier: q
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_arrays_and_initializers() {
+        let result = parse_to_string(
+            "i32[2][N + 1] grid = {{1, 2}, {}};\nPoint p = {.x = 1, .y = -y,};\nvoid f() { buf[0] = 1; }",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Variable
+    TypeVarPair
+      Type (array)
+        Type (array)
+          Type
+            Identifier: i32
+          Number: 2
+        BinaryOperation: +
+          Identifier: N
+          Number: 1
+      Identifier: grid
+    InitializerList
+      InitializerList
+        Number: 1
+        Number: 2
+      InitializerList
+  Variable
+    TypeVarPair
+      Type
+        Identifier: Point
+      Identifier: p
+    InitializerList
+      FieldInitializer
+        Identifier: x
+        Number: 1
+      FieldInitializer
+        Identifier: y
+        PrefixOperation: -
+          Identifier: y
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      Expression
+        BinaryOperation: =
+          Index
+            Identifier: buf
+            Number: 0
+          Number: 1
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_parenthesized_index_exprs() {
+        let result = parse_to_string(
+            "void f() { x = (arr[0]); y = (arr[i]) + 1; z = (arr[i]) & m; w = (Point[2]) p; }",
+        );
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      Expression
+        BinaryOperation: =
+          Identifier: x
+          Index
+            Identifier: arr
+            Number: 0
+      Expression
+        BinaryOperation: =
+          Identifier: y
+          BinaryOperation: +
+            Index
+              Identifier: arr
+              Identifier: i
+            Number: 1
+      Expression
+        BinaryOperation: =
+          Identifier: z
+          BinaryOperation: &
+            Index
+              Identifier: arr
+              Identifier: i
+            Identifier: m
+      Expression
+        BinaryOperation: =
+          Identifier: w
+          Cast
+            Type (array)
+              Type
+                Identifier: Point
+              Number: 2
+            Identifier: p
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_parentheses() {
+        let result = parse_to_string("void f() { x = ((a)); y = ((a)) + 1; }");
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      Expression
+        BinaryOperation: =
+          Identifier: x
+          Identifier: a
+      Expression
+        BinaryOperation: =
+          Identifier: y
+          BinaryOperation: +
+            Identifier: a
+            Number: 1
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_deeply_nested_index_exprs() {
+        let depth = 64;
+        let src_code = format!("i32 x = {}1{};", "(a[".repeat(depth), "])".repeat(depth));
+        let (tokens, _) = Lexer::new(&src_code).tokenize_all();
+        assert!(Parser::new(String::from("test"), &tokens).parse().is_ok());
+    }
+
+    #[test]
+    fn test_parse_arrays_and_initializers_errors() {
+        let result = parse_to_string("i32[4 xs;\ni32 ys = {1 2};\ni32 zs = {,};");
+        assert_eq!(
+            result,
+            Err(vec![
+                String::from("Expected ']'. (Token: (Identifier, Name: xs))"),
+                String::from("Expected ',' or '}'. (Token: (Number, whole number: 2))"),
+                String::from("Expected operand. (Token: (Reserved Char, Value: ','))"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_lexer_error_tokens() {
+        let result = parse_to_string("i32 x = @ + 1;\ni32 y = 0b;\ni32 z = (1;");
+        assert_eq!(
+            result.unwrap_err(),
+            vec![String::from(
+                "Expected ')'. (Token: (Reserved Char, Value: ';'))"
+            )]
+        );
+        let result = parse_to_string("i32 x = @ + 1;");
+        assert_eq!(
+            result,
+            Ok(String::from(
+                "Program: test
+  Variable
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: x
+    BinaryOperation: +
+      Error: @
+      Number: 1
+"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_unterminated_literal_keeps_terminator() {
+        let src_code = "i32 a = \"open;\ni32 b = 1;";
+        let (tokens, lexer_errors) = Lexer::new(src_code).tokenize_all();
+        assert_eq!(lexer_errors.len(), 1);
+        let result = Parser::new(String::from("test"), &tokens).parse();
+        assert!(result.is_ok());
+        let src_code = "i32 a = \"open;\ni32 b = \"x\";";
+        let (tokens, lexer_errors) = Lexer::new(src_code).tokenize_all();
+        assert_eq!(lexer_errors.len(), 1);
+        let span = lexer_errors[0].to_diagnostic().resolve_span(src_code);
+        assert_eq!((span.start.line, span.start.column), (1, 9));
+        let result = Parser::new(String::from("test"), &tokens).parse();
+        assert!(result.is_ok());
+        let src_code = "i32 a = r#\"open;\ni32 b = 1;\ni32 c = ;";
+        let (tokens, lexer_errors) = Lexer::new(src_code).tokenize_all();
+        assert_eq!(lexer_errors.len(), 1);
+        let errors = Parser::new(String::from("test"), &tokens)
+            .parse()
+            .err()
+            .unwrap();
+        let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec!["Expected operand. (Token: (Reserved Char, Value: ';'))"]
+        );
+    }
+
+    #[test]
+    fn test_parse_spans() {
+        let (tokens, _) = Lexer::new("i32 x = 1 +\n  foo(2);\nstruct P { i32 a; };").tokenize_all();
+        let program = Parser::new(String::from("test"), &tokens)
+            .parse()
+            .ok()
+            .unwrap();
+        let line_column = |node: &super::ASTNode| {
+            let span = node.span();
+            (
+                (span.start.line, span.start.column),
+                (span.end.line, span.end.column),
+            )
+        };
+        assert_eq!(line_column(&program), ((1, 1), (3, 20)));
+        let definitions = match program.kind() {
+            ASTNodeKind::Program(info) => info.definitions(),
+            _ => panic!("Expected a program."),
+        };
+        assert_eq!(line_column(&definitions[0]), ((1, 1), (2, 9)));
+        assert_eq!(line_column(&definitions[1]), ((3, 1), (3, 20)));
+
+        let (tokens, _) = Lexer::new("i32 x = ;\ni32 y").tokenize_all();
+        let errors = Parser::new(String::from("test"), &tokens)
+            .parse()
+            .err()
+            .unwrap();
+        let range = errors[0].position_range().unwrap();
+        assert_eq!((range.start.line, range.start.column), (1, 9));
+        assert_eq!(errors[1].description(), "Expected ';', '=' or '('.");
+        assert!(errors[1].position_range().is_none());
+    }
+
+    #[test]
+    fn test_parse_error_diagnostics() {
+        let src_code = "i32 x = 1 +;\ni32 y";
+        let (tokens, _) = Lexer::new(src_code).tokenize_all();
+        let errors = Parser::new(String::from("test"), &tokens)
+            .parse()
+            .err()
+            .unwrap();
+        let rendered: Vec<String> = errors
+            .iter()
+            .map(|err| err.to_diagnostic().render("test.chia", src_code))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![
+                "error[E0101]: Expected operand.
+ --> test.chia:1:12
+  |
+1 | i32 x = 1 +;
+  |            ^
+",
+                "error[E0102]: Expected ';', '=' or '('.
+ --> test.chia:2:6
+  |
+2 | i32 y
+  |      ^
+  = note: the file ended before this construct was complete
+",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_never_panics() {
+        const PIECES: [&str; 48] = [
+            "i32", "x", "y", "f", "(", ")", "{", "}", "[", "]", ";", ",", "=", "+", "-", "*", "&",
+            "!", "?", ":", ".", "++", "--", "<<=", "==", "1", "2.5", "\"s\"", "'c'", "@", "if",
+            "else", "while", "do", "for", "switch", "case", "default", "return", "break",
+            "continue", "struct", "enum", "typedef", "static", "mut", "\n", "/// doc",
+        ];
+        // A fixed xorshift generator keeps failures reproducible.
+        let mut state: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for _ in 0..3000 {
+            let length = next() % 40;
+            let src_code: Vec<&str> = (0..length)
+                .map(|_| PIECES[(next() % PIECES.len() as u64) as usize])
+                .collect();
+            let src_code = src_code.join(" ");
+            let (tokens, _) = Lexer::new(&src_code).tokenize_all();
+            let _ = Parser::new(String::from("fuzz"), &tokens).parse();
         }
     }
+
+    #[test]
+    fn test_parse_internal_error() {
+        let operator = ReservedToken::Operator(
+            "<>",
+            OperatorInfo {
+                is_prefix: false,
+                is_postfix: false,
+                is_binary: true,
+                is_ternary: false,
+                precedence: None,
+                associativity: Associativity::Left,
+            },
+        );
+        let (mut tokens, _) = Lexer::new("i32 z = x + y * w;").tokenize_all();
+        tokens[4].0 = Token::Reserved(&operator);
+        let errors = Parser::new(String::from("test"), &tokens)
+            .parse()
+            .err()
+            .unwrap();
+        assert!(errors[0].is_internal());
+        let diagnostic = errors[0].to_diagnostic();
+        assert_eq!(diagnostic.code(), "E0900");
+        assert_eq!(
+            diagnostic.message(),
+            "Internal compiler error: An operator without a precedence was treated as binary."
+        );
+    }
+
+    #[test]
+    fn test_parse_error_recovery() {
+        let src_code = "i32 f() {
+  i32 x = ;
+  x = 1;
+  if (x { y; }
+  return x;
+}
+struct P { i32 a; i32 ; u8 b; };
+i32 g(,) { broken; }
+enum E { A };
+i32 h() {
+  h();
+struct Q { i32 c; };";
+        let (tokens, _) = Lexer::new(src_code).tokenize_all();
+        let (program, errors) = Parser::new(String::from("test"), &tokens).parse_partial();
+        let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Expected operand. (Token: (Reserved Char, Value: ';'))",
+                "Expected ')'. (Token: (Reserved Char, Value: '{'))",
+                "Expected an identifier. (Token: (Reserved Char, Value: ';'))",
+                "Expected an identifier or tuple. (Token: (Reserved Char, Value: ','))",
+                "Expected '}'. (Token: (Keyword, Name: struct))",
+            ]
+        );
+        assert_eq!(
+            program.to_string(),
+            "Program: test
+  Function
+    Type
+      Identifier: i32
+    Identifier: f
+    Sequence
+      Expression
+        BinaryOperation: =
+          Identifier: x
+          Number: 1
+      Return
+        Identifier: x
+  StructDef
+    Identifier: P
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: a
+    TypeVarPair
+      Type
+        Identifier: u8
+      Identifier: b
+  Function
+    Type
+      Identifier: i32
+    Identifier: g
+    Sequence
+      Expression
+        Identifier: broken
+  EnumDef
+    Identifier: E
+    EnumVariant
+      Identifier: A
+  Function
+    Type
+      Identifier: i32
+    Identifier: h
+    Sequence
+      Expression
+        FunctionCall
+          Identifier: h
+  StructDef
+    Identifier: Q
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: c
+"
+        );
+    }
+
+    #[test]
+    fn test_parse_switch_and_enum_recovery() {
+        let src_code = "i32 f() {
+  switch (x) { case 1: a = ; b = 2; c = ; default: d = 3; }
+}
+enum E { A = , B, C = ;, D };";
+        let (tokens, _) = Lexer::new(src_code).tokenize_all();
+        let (program, errors) = Parser::new(String::from("test"), &tokens).parse_partial();
+        let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+        assert_eq!(
+            errors,
+            vec![
+                "Expected operand. (Token: (Reserved Char, Value: ';'))",
+                "Expected operand. (Token: (Reserved Char, Value: ';'))",
+                "Expected operand. (Token: (Reserved Char, Value: ','))",
+                "Expected operand. (Token: (Reserved Char, Value: ';'))",
+            ]
+        );
+        assert_eq!(
+            program.to_string(),
+            "Program: test
+  Function
+    Type
+      Identifier: i32
+    Identifier: f
+    Sequence
+      ControlFlow: Switch
+        Identifier: x
+        Sequence
+          ControlFlow: SwitchCase
+            Number: 1
+            Sequence
+              Expression
+                BinaryOperation: =
+                  Identifier: b
+                  Number: 2
+          ControlFlow: SwitchDefault
+            Sequence
+              Expression
+                BinaryOperation: =
+                  Identifier: d
+                  Number: 3
+  EnumDef
+    Identifier: E
+    EnumVariant
+      Identifier: B
+    EnumVariant
+      Identifier: D
+"
+        );
+    }
+
+    #[test]
+    fn test_parse_nested_errors_reported_once() {
+        let parse_errors = |src_code: &str| {
+            let (tokens, _) = Lexer::new(src_code).tokenize_all();
+            let (program, errors) = Parser::new(String::from("test"), &tokens).parse_partial();
+            let errors: Vec<String> = errors.iter().map(|err| err.to_string()).collect();
+            (program.to_string(), errors)
+        };
+        let (_, errors) = parse_errors("void f() { if (a) { i32 x = ; } else }");
+        assert_eq!(
+            errors,
+            vec![
+                "Expected operand. (Token: (Reserved Char, Value: ';'))",
+                "Expected operand. (Token: (Reserved Char, Value: '}'))",
+            ]
+        );
+        let (_, errors) = parse_errors("i32 g(,) { i32 y = ; }");
+        assert_eq!(
+            errors,
+            vec![
+                "Expected an identifier or tuple. (Token: (Reserved Char, Value: ','))",
+                "Expected operand. (Token: (Reserved Char, Value: ';'))",
+            ]
+        );
+        let (program, errors) = parse_errors("void f() { for (i = ; ; ) { g(); } }");
+        assert_eq!(
+            errors,
+            vec!["Expected operand. (Token: (Reserved Char, Value: ';'))"]
+        );
+        assert_eq!(
+            program,
+            "Program: test
+  Function
+    Type
+      Identifier: void
+    Identifier: f
+    Sequence
+      ForLoop
+        Sequence
+          Expression
+            FunctionCall
+              Identifier: g
+"
+        );
+    }
 }
@@ -0,0 +1,297 @@
+use std::fmt;
+
+use super::position::{Position, PositionRange};
+
+pub const INVALID_CHARACTER: &str = "E0001";
+// Unterminated literals and block comments.
+pub const UNTERMINATED_TOKEN: &str = "E0002";
+pub const INVALID_ESCAPE: &str = "E0003";
+pub const INVALID_NUMBER: &str = "E0004";
+pub const INVALID_CHAR_LITERAL: &str = "E0005";
+pub const UNEXPECTED_TOKEN: &str = "E0101";
+pub const UNEXPECTED_END_OF_FILE: &str = "E0102";
+// Input files that could not be opened or read.
+pub const UNREADABLE_FILE: &str = "E0800";
+pub const INTERNAL_COMPILER_ERROR: &str = "E0900";
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Note => write!(f, "note"),
+        }
+    }
+}
+
+pub struct Diagnostic {
+    severity: Severity,
+    code: &'static str,
+    message: String,
+    // None when the problem is at the end of the file.
+    span: Option<PositionRange>,
+    notes: Vec<String>,
+    help: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(
+        severity: Severity,
+        code: &'static str,
+        message: String,
+        span: Option<PositionRange>,
+    ) -> Diagnostic {
+        Diagnostic {
+            severity,
+            code,
+            message,
+            span,
+            notes: Vec::new(),
+            help: Vec::new(),
+        }
+    }
+
+    pub fn with_note(mut self, note: String) -> Diagnostic {
+        self.notes.push(note);
+        self
+    }
+
+    pub fn with_help(mut self, help: String) -> Diagnostic {
+        self.help.push(help);
+        self
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn notes(&self) -> &[String] {
+        &self.notes
+    }
+
+    pub fn help(&self) -> &[String] {
+        &self.help
+    }
+
+    // The span of the diagnostic, falling back to the end of `src_code`.
+    pub fn resolve_span(&self, src_code: &str) -> PositionRange {
+        match &self.span {
+            Some(span) => span.clone(),
+            None => {
+                let mut end = Position::new();
+                src_code.chars().for_each(|c| end.advance(c));
+                PositionRange {
+                    start: end.clone(),
+                    end,
+                }
+            }
+        }
+    }
+
+    // Renders the diagnostic like rustc does, quoting the offending source line.
+    pub fn render(&self, file_name: &str, src_code: &str) -> String {
+        let span = self.resolve_span(src_code);
+        let line_text = src_code
+            .lines()
+            .nth(span.start.line - 1)
+            .unwrap_or("")
+            .trim_end_matches('\r');
+        let caret_count = match span.end.line == span.start.line {
+            true => span.end.column.saturating_sub(span.start.column) + 1,
+            _ => (line_text.chars().count() + 1).saturating_sub(span.start.column),
+        };
+        let line_number = span.start.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let mut text = format!("{}[{}]: {}\n", self.severity, self.code, self.message);
+        text.push_str(&format!(
+            "{}--> {}:{}:{}\n",
+            gutter, file_name, span.start.line, span.start.column
+        ));
+        text.push_str(&format!("{} |\n", gutter));
+        text.push_str(&format!("{} | {}\n", line_number, line_text));
+        text.push_str(&format!(
+            "{} | {}{}\n",
+            gutter,
+            " ".repeat(span.start.column - 1),
+            "^".repeat(caret_count.max(1))
+        ));
+        for note in self.notes.iter() {
+            text.push_str(&format!("{} = note: {}\n", gutter, note));
+        }
+        for help in self.help.iter() {
+            text.push_str(&format!("{} = help: {}\n", gutter, help));
+        }
+        text
+    }
+
+    // Renders the diagnostic as a single-line JSON object for tools.
+    pub fn to_json(&self, file_name: &str, src_code: &str) -> String {
+        let span = self.resolve_span(src_code);
+        let position_json = |position: &Position| {
+            format!(
+                "{{\"line\":{},\"column\":{},\"index\":{}}}",
+                position.line, position.column, position.index
+            )
+        };
+        self.json_with_span(
+            file_name,
+            format!(
+                "{{\"start\":{},\"end\":{}}}",
+                position_json(&span.start),
+                position_json(&span.end)
+            ),
+        )
+    }
+
+    // Renders a diagnostic about a whole file, such as one that cannot be read, whose span is null.
+    pub fn to_file_json(&self, file_name: &str) -> String {
+        self.json_with_span(file_name, String::from("null"))
+    }
+
+    fn json_with_span(&self, file_name: &str, span_json: String) -> String {
+        let strings_json = |strings: &[String]| {
+            let items: Vec<String> = strings.iter().map(|s| json_string(s)).collect();
+            format!("[{}]", items.join(","))
+        };
+        format!(
+            "{{\"file\":{},\"span\":{},\"severity\":\"{}\",\"code\":\"{}\",\"message\":{},\"notes\":{},\"help\":{}}}",
+            json_string(file_name),
+            span_json,
+            self.severity,
+            self.code,
+            json_string(&self.message),
+            strings_json(&self.notes),
+            strings_json(&self.help)
+        )
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagnostic, Severity, INVALID_CHARACTER, UNEXPECTED_END_OF_FILE};
+    use crate::common::position::{Position, PositionRange};
+
+    fn position(line: usize, column: usize, index: usize) -> Position {
+        Position {
+            line,
+            column,
+            index,
+        }
+    }
+
+    #[test]
+    fn test_diagnostic_render() {
+        let src_code = "i32 x = 1;\ni32 y = @@;\n";
+        let diagnostic = Diagnostic::new(
+            Severity::Error,
+            INVALID_CHARACTER,
+            String::from("Invalid character '@' found."),
+            Some(PositionRange {
+                start: position(2, 9, 19),
+                end: position(2, 10, 20),
+            }),
+        )
+        .with_note(String::from("identifiers may not contain '@'"))
+        .with_help(String::from("remove the character"));
+        assert_eq!(
+            diagnostic.render("main.chia", src_code),
+            "error[E0001]: Invalid character '@' found.
+ --> main.chia:2:9
+  |
+2 | i32 y = @@;
+  |         ^^
+  = note: identifiers may not contain '@'
+  = help: remove the character
+"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_render_multi_line_and_end_of_file() {
+        let src_code = "s = \"open\nstill open";
+        let diagnostic = Diagnostic::new(
+            Severity::Warning,
+            INVALID_CHARACTER,
+            String::from("Spans two lines."),
+            Some(PositionRange {
+                start: position(1, 5, 4),
+                end: position(2, 4, 13),
+            }),
+        );
+        assert_eq!(
+            diagnostic.render("a.chia", src_code),
+            "warning[E0001]: Spans two lines.
+ --> a.chia:1:5
+  |
+1 | s = \"open
+  |     ^^^^^
+"
+        );
+        let diagnostic = Diagnostic::new(
+            Severity::Error,
+            UNEXPECTED_END_OF_FILE,
+            String::from("Expected ';'."),
+            None,
+        );
+        assert_eq!(
+            diagnostic.render("a.chia", src_code),
+            "error[E0102]: Expected ';'.
+ --> a.chia:2:11
+  |
+2 | still open
+  |           ^
+"
+        );
+    }
+
+    #[test]
+    fn test_diagnostic_to_json() {
+        let diagnostic = Diagnostic::new(
+            Severity::Error,
+            INVALID_CHARACTER,
+            String::from("Invalid character '\"' in \\path\n."),
+            Some(PositionRange {
+                start: position(1, 2, 1),
+                end: position(1, 2, 1),
+            }),
+        )
+        .with_help(String::from("remove\tit"));
+        assert_eq!(
+            diagnostic.to_json("dir/a.chia", "x\"\u{1}"),
+            "{\"file\":\"dir/a.chia\",\"span\":{\"start\":{\"line\":1,\"column\":2,\"index\":1},\"end\":{\"line\":1,\"column\":2,\"index\":1}},\"severity\":\"error\",\"code\":\"E0001\",\"message\":\"Invalid character '\\\"' in \\\\path\\n.\",\"notes\":[],\"help\":[\"remove\\tit\"]}"
+        );
+        assert_eq!(super::json_string("a\u{1}b"), "\"a\\u0001b\"");
+    }
+}
@@ -1,3 +1,4 @@
+pub mod diagnostic;
 pub mod position;
 pub mod reserved;
 pub mod token;
@@ -1,7 +1,10 @@
+use std::fmt;
+
 #[derive(Clone)]
 pub struct Position {
     pub line: usize,
     pub column: usize,
+    // Byte offset into the source code, always on a char boundary.
     pub index: usize,
 }
 
@@ -11,6 +14,12 @@ pub struct PositionRange {
     pub end: Position,
 }
 
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Position {
     pub fn new() -> Position {
         Position {
@@ -19,24 +28,48 @@ impl Position {
             index: 0,
         }
     }
+
+    pub fn advance(&mut self, c: char) {
+        if c == '\n' {
+            self.line += 1;
+            self.column = 0;
+        }
+        self.column += 1;
+        self.index += c.len_utf8();
+    }
+
+    // Column in UTF-16 code units, as expected by most editors.
+    pub fn utf16_column(&self, src_code: &str) -> usize {
+        let line_start = src_code[..self.index]
+            .rfind('\n')
+            .map_or(0, |newline| newline + 1);
+        src_code[line_start..self.index].encode_utf16().count() + 1
+    }
 }
 
-impl ToString for Position {
-    fn to_string(&self) -> String {
-        format!(
+impl PositionRange {
+    // Spans from the start of this range to the end of `other`.
+    pub fn join(&self, other: &PositionRange) -> PositionRange {
+        PositionRange {
+            start: self.start.clone(),
+            end: other.end.clone(),
+        }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
             "(Line: {}, Column: {}, Index: {})",
             self.line, self.column, self.index
         )
     }
 }
 
-impl ToString for PositionRange {
-    fn to_string(&self) -> String {
-        format!(
-            "{{Start: {}, End: {}}}",
-            self.start.to_string(),
-            self.end.to_string()
-        )
+impl fmt::Display for PositionRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{Start: {}, End: {}}}", self.start, self.end)
     }
 }
 
@@ -52,6 +85,45 @@ mod tests {
         assert_eq!(pos.index, 0);
     }
 
+    #[test]
+    fn test_position_advance() {
+        let mut pos = Position::new();
+        pos.advance('a');
+        assert_eq!((pos.line, pos.column, pos.index), (1, 2, 1));
+        pos.advance('\n');
+        assert_eq!((pos.line, pos.column, pos.index), (2, 1, 2));
+        pos.advance('b');
+        assert_eq!((pos.line, pos.column, pos.index), (2, 2, 3));
+        pos.advance('é');
+        assert_eq!((pos.line, pos.column, pos.index), (2, 3, 5));
+    }
+
+    #[test]
+    fn test_position_utf16_column() {
+        let src_code = "a\n😀é;";
+        let mut pos = Position::new();
+        for c in src_code.chars().take(4) {
+            pos.advance(c);
+        }
+        assert_eq!((pos.column, pos.utf16_column(src_code)), (3, 4));
+    }
+
+    #[test]
+    fn test_position_range_join() {
+        let mut end = Position::new();
+        end.advance('a');
+        let first = PositionRange {
+            start: Position::new(),
+            end: Position::new(),
+        };
+        let second = PositionRange {
+            start: end.clone(),
+            end,
+        };
+        let joined = first.join(&second);
+        assert_eq!((joined.start.index, joined.end.index), (0, 1));
+    }
+
     #[test]
     fn test_position_to_string() {
         let pos = Position {
@@ -98,11 +170,7 @@ mod tests {
         };
         assert_eq!(
             range.to_string(),
-            format!(
-                "{{Start: {}, End: {}}}",
-                range.start.to_string(),
-                range.end.to_string()
-            )
+            format!("{{Start: {}, End: {}}}", range.start, range.end)
         );
     }
 }
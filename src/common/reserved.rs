@@ -1,9 +1,16 @@
+#[derive(PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
 pub struct OperatorInfo {
     pub is_prefix: bool,
     pub is_postfix: bool,
     pub is_binary: bool,
     pub is_ternary: bool,
     pub precedence: Option<u32>,
+    pub associativity: Associativity,
 }
 
 pub enum ReservedToken<'a> {
@@ -1,56 +1,87 @@
+use std::fmt;
+
 use super::reserved::ReservedToken;
 
 pub struct NumberInfo<'a> {
+    // Digits may contain `_` separators and exclude any radix prefix.
     pub whole_number: &'a str,
     pub fractional_part: Option<&'a str>,
+    // Includes the sign when one is written, e.g. `-3` for `1.5e-3`.
+    pub exponent: Option<&'a str>,
+    pub suffix: Option<&'a str>,
+    pub radix: u32,
 }
 
-impl<'a> ToString for NumberInfo<'a> {
-    fn to_string(&self) -> String {
-        match self.fractional_part {
-            Some(f) => format!(
-                "(Number, whole number: {}, fractional part: {})",
-                self.whole_number, f
-            ),
-            _ => format!("(Number, whole number: {})", self.whole_number),
+impl<'a> fmt::Display for NumberInfo<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(Number, whole number: {}", self.whole_number)?;
+        if let Some(fractional) = self.fractional_part {
+            write!(f, ", fractional part: {}", fractional)?;
+        }
+        if let Some(exponent) = self.exponent {
+            write!(f, ", exponent: {}", exponent)?;
+        }
+        if self.radix != 10 {
+            write!(f, ", radix: {}", self.radix)?;
         }
+        if let Some(suffix) = self.suffix {
+            write!(f, ", suffix: {}", suffix)?;
+        }
+        write!(f, ")")
     }
 }
 
+pub struct StrInfo<'a> {
+    // The literal as written in the source, quotes included.
+    pub raw: &'a str,
+    pub value: String,
+    pub is_raw: bool,
+}
+
+pub struct CharInfo<'a> {
+    // The literal as written in the source, quotes included.
+    pub raw: &'a str,
+    pub value: char,
+}
+
 pub enum Token<'a> {
     Identifier(&'a str),
     Reserved(&'a ReservedToken<'a>),
     Number(NumberInfo<'a>),
-    Str(&'a str),
-    Char(&'a str),
+    Str(StrInfo<'a>),
+    Char(CharInfo<'a>),
+    DocComment(&'a str),
+    // Source text skipped by the lexer while recovering from an error.
+    Error(&'a str),
 }
 
-impl<'a> ToString for Token<'a> {
-    fn to_string(&self) -> String {
+impl<'a> fmt::Display for Token<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::Identifier(s) => format!("(Identifier, Name: {})", s),
+            Self::Identifier(s) => write!(f, "(Identifier, Name: {})", s),
             Self::Reserved(token) => match token {
-                ReservedToken::Char(c) => {
-                    if *c != '\n' {
-                        return format!("(Reserved Char, Value: '{}')", c);
-                    }
-                    format!("(Reserved Char, Value: '\\n')")
-                }
-                ReservedToken::Keyword(keyword) => format!("(Keyword, Name: {})", keyword),
-                ReservedToken::Operator(op, _) => format!("(Operator, Value: '{}')", op),
+                ReservedToken::Char('\n') => write!(f, "(Reserved Char, Value: '\\n')"),
+                ReservedToken::Char(c) => write!(f, "(Reserved Char, Value: '{}')", c),
+                ReservedToken::Keyword(keyword) => write!(f, "(Keyword, Name: {})", keyword),
+                ReservedToken::Operator(op, _) => write!(f, "(Operator, Value: '{}')", op),
             },
-            Self::Number(info) => info.to_string(),
-            Self::Char(c) => format!("(Char Literal, Value: {})", c),
-            Self::Str(s) => format!("(String Literal, Value: {})", s),
+            Self::Number(info) => write!(f, "{}", info),
+            Self::Char(info) => write!(f, "(Char Literal, Value: {})", info.raw),
+            Self::Str(info) if info.is_raw => {
+                write!(f, "(Raw String Literal, Value: {})", info.raw)
+            }
+            Self::Str(info) => write!(f, "(String Literal, Value: {})", info.raw),
+            Self::DocComment(s) => write!(f, "(Doc Comment, Value: {})", s),
+            Self::Error(s) => write!(f, "(Error, Text: {})", s),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::common::reserved::{OperatorInfo, ReservedToken};
+    use crate::common::reserved::{Associativity, OperatorInfo, ReservedToken};
 
-    use super::{NumberInfo, Token};
+    use super::{CharInfo, NumberInfo, StrInfo, Token};
 
     #[test]
     fn test_number_info_to_string() {
@@ -58,7 +89,10 @@ mod tests {
         assert_eq!(
             NumberInfo {
                 whole_number,
-                fractional_part: None
+                fractional_part: None,
+                exponent: None,
+                suffix: None,
+                radix: 10,
             }
             .to_string(),
             format!("(Number, whole number: {})", whole_number)
@@ -69,7 +103,10 @@ mod tests {
         assert_eq!(
             NumberInfo {
                 whole_number,
-                fractional_part: Some(fractional_part)
+                fractional_part: Some(fractional_part),
+                exponent: None,
+                suffix: None,
+                radix: 10,
             }
             .to_string(),
             format!(
@@ -77,6 +114,35 @@ mod tests {
                 whole_number, fractional_part
             )
         );
+
+        let whole_number = "FF_FF";
+        assert_eq!(
+            NumberInfo {
+                whole_number,
+                fractional_part: None,
+                exponent: None,
+                suffix: None,
+                radix: 16,
+            }
+            .to_string(),
+            format!("(Number, whole number: {}, radix: 16)", whole_number)
+        );
+
+        let whole_number = "6";
+        assert_eq!(
+            NumberInfo {
+                whole_number,
+                fractional_part: Some("02"),
+                exponent: Some("+23"),
+                suffix: Some("f64"),
+                radix: 10,
+            }
+            .to_string(),
+            format!(
+                "(Number, whole number: {}, fractional part: 02, exponent: +23, suffix: f64)",
+                whole_number
+            )
+        );
     }
 
     #[test]
@@ -122,6 +188,7 @@ mod tests {
             is_prefix: true,
             is_ternary: true,
             precedence: None,
+            associativity: Associativity::Right,
         };
         let reserved_token = ReservedToken::Operator(op, op_info);
         assert_eq!(
@@ -133,6 +200,9 @@ mod tests {
         let num_info = NumberInfo {
             whole_number,
             fractional_part: Some(fractional_part),
+            exponent: None,
+            suffix: None,
+            radix: 10,
         };
         assert_eq!(
             Token::Number(num_info).to_string(),
@@ -143,18 +213,42 @@ mod tests {
         );
         let char_literal = "'a'";
         assert_eq!(
-            Token::Char(char_literal).to_string(),
+            Token::Char(CharInfo {
+                raw: char_literal,
+                value: 'a',
+            })
+            .to_string(),
             format!("(Char Literal, Value: {})", char_literal)
         );
         let char_literal = "'\\n'";
         assert_eq!(
-            Token::Char(char_literal).to_string(),
+            Token::Char(CharInfo {
+                raw: char_literal,
+                value: '\n',
+            })
+            .to_string(),
             format!("(Char Literal, Value: {})", char_literal)
         );
         let str_literal = "\"This is a test.\"";
         assert_eq!(
-            Token::Str(str_literal).to_string(),
+            Token::Str(StrInfo {
+                raw: str_literal,
+                value: String::from("This is a test."),
+                is_raw: false,
+            })
+            .to_string(),
             format!("(String Literal, Value: {})", str_literal)
         );
+        let str_literal = "r#\"SELECT \"id\"\"#";
+        assert_eq!(
+            Token::Str(StrInfo {
+                raw: str_literal,
+                value: String::from("SELECT \"id\""),
+                is_raw: true,
+            })
+            .to_string(),
+            format!("(Raw String Literal, Value: {})", str_literal)
+        );
+        assert_eq!(Token::Error("\"open").to_string(), "(Error, Text: \"open)");
     }
 }
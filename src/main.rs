@@ -1,23 +1,57 @@
-mod chia;
-mod common;
-
 use std::fs::File;
 use std::io::Read;
 use std::{process::exit, vec::Vec};
 
-use chia::lexer::Lexer;
+use chia_compiler::chia::lexer::Lexer;
+use chia_compiler::chia::parser::Parser;
+use chia_compiler::common::diagnostic::{Diagnostic, Severity, UNREADABLE_FILE};
 
 const VERSION: (u32, u32, u32) = (0, 0, 1);
 
-const HELP_INFO: &str = "Flags:\n-v, --verbose: Verbose Mode";
+const HELP_INFO: &str = "Flags:\n-v, --verbose: Verbose Mode\n--emit=<stage>: Stop after the given stage and print its output (tokens, ast)\n--error-format=<format>: How errors are printed (human, json)";
+
+#[derive(Clone, Copy, PartialEq)]
+enum EmitStage {
+    Tokens,
+    Ast,
+}
+
+impl EmitStage {
+    fn from_name(name: &str) -> Option<EmitStage> {
+        match name {
+            "tokens" => Some(EmitStage::Tokens),
+            "ast" => Some(EmitStage::Ast),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ErrorFormat {
+    Human,
+    Json,
+}
+
+impl ErrorFormat {
+    fn from_name(name: &str) -> Option<ErrorFormat> {
+        match name {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
 
 struct Setting {
     verbose: bool,
+    emit: Option<EmitStage>,
+    error_format: ErrorFormat,
     input_files: Vec<String>,
 }
 
+// The banner goes to stderr so that the output of `--emit` can be piped.
 fn print_info() {
-    println!(
+    eprintln!(
         "Chia Compiler -- Version: {}.{}.{}",
         VERSION.0, VERSION.1, VERSION.2
     );
@@ -26,27 +60,49 @@ fn print_info() {
 fn print_usage() {
     println!(
         "Usage: {} <flags> <input files>",
-        std::env::args().into_iter().next().unwrap()
+        std::env::args().next().unwrap()
     );
     println!("{}", HELP_INFO);
 }
 
-fn parse_args() -> Setting {
+fn parse_args() -> Result<Setting, String> {
     let mut verbose = false;
+    let mut emit = None;
+    let mut error_format = ErrorFormat::Human;
     let mut input_files = Vec::new();
     for arg in std::env::args().skip(1) {
         match arg.as_str() {
             "-v" | "--verbose" => verbose = true,
-            _ => input_files.push(arg),
+            _ => match arg.strip_prefix("--emit=") {
+                Some(stage_name) => match EmitStage::from_name(stage_name) {
+                    Some(stage) => emit = Some(stage),
+                    None => return Err(format!("Unknown stage for --emit: {}", stage_name)),
+                },
+                None => match arg.strip_prefix("--error-format=") {
+                    Some(format_name) => match ErrorFormat::from_name(format_name) {
+                        Some(format) => error_format = format,
+                        None => {
+                            return Err(format!(
+                                "Unknown format for --error-format: {}",
+                                format_name
+                            ))
+                        }
+                    },
+                    None => input_files.push(arg),
+                },
+            },
         }
     }
-    Setting {
-        verbose: verbose,
+    Ok(Setting {
+        verbose,
+        emit,
+        error_format,
         input_files,
-    }
+    })
 }
 
-fn read_files(setting: &Setting) -> Result<Vec<String>, String> {
+// On failure, returns the name of the offending file along with a description.
+fn read_files(setting: &Setting) -> Result<Vec<String>, (String, String)> {
     let mut src_code_strs = Vec::new();
     for file_name in &setting.input_files {
         match File::open(file_name) {
@@ -55,19 +111,17 @@ fn read_files(setting: &Setting) -> Result<Vec<String>, String> {
                 match f.read_to_string(&mut content) {
                     Ok(_) => src_code_strs.push(content),
                     Err(err) => {
-                        return Err(format!(
-                            "Unable to read the file: {}\nReason: {}",
-                            file_name,
-                            err.to_string()
+                        return Err((
+                            file_name.clone(),
+                            format!("Unable to read the file: {}\nReason: {}", file_name, err),
                         ));
                     }
                 }
             }
             Err(err) => {
-                return Err(format!(
-                    "Unable to open the file: {}\nReason: {}",
-                    file_name,
-                    err.to_string()
+                return Err((
+                    file_name.clone(),
+                    format!("Unable to open the file: {}\nReason: {}", file_name, err),
                 ));
             }
         }
@@ -75,45 +129,84 @@ fn read_files(setting: &Setting) -> Result<Vec<String>, String> {
     Ok(src_code_strs)
 }
 
-fn process_src_code(setting: &Setting, src_contents: Vec<String>) {
-    for content in &src_contents {
-        let mut lexer = Lexer::new(content);
-        let mut all_tokens = Vec::new();
-        loop {
-            match lexer.next_token() {
-                Ok(None) => break,
-                Ok(Some((token, pos_info))) => {
-                    if setting.verbose {
-                        println!(
-                            "Token: {}\nPosition: {}",
-                            token.to_string(),
-                            pos_info.to_string()
-                        );
-                    }
-                    all_tokens.push(token);
+// Diagnostics go to stderr so that stdout only carries the output of the stages.
+fn report(setting: &Setting, diagnostic: Diagnostic, file_name: &str, src_code: &str) {
+    match setting.error_format {
+        ErrorFormat::Human => eprintln!("{}", diagnostic.render(file_name, src_code)),
+        ErrorFormat::Json => eprintln!("{}", diagnostic.to_json(file_name, src_code)),
+    }
+}
+
+fn process_src_code(setting: &Setting, src_contents: Vec<String>) -> bool {
+    let mut succeeded = true;
+    for (file_name, content) in setting.input_files.iter().zip(src_contents.iter()) {
+        let (tokens, errors) = Lexer::new(content).tokenize_all();
+        if setting.verbose || setting.emit == Some(EmitStage::Tokens) {
+            for (token, pos_info) in tokens.iter() {
+                println!("Token: {}\nPosition: {}", token, pos_info);
+            }
+        }
+        for err in errors.iter() {
+            report(setting, err.to_diagnostic(), file_name, content);
+        }
+        // The parser still runs on the error tokens so that later mistakes are reported too.
+        let lexer_failed = !errors.is_empty();
+        succeeded &= !lexer_failed;
+        if setting.emit == Some(EmitStage::Tokens) {
+            continue;
+        }
+        let mut parser = Parser::new(file_name.clone(), &tokens);
+        match parser.parse() {
+            Ok(program) => {
+                if setting.emit == Some(EmitStage::Ast) && !lexer_failed {
+                    print!("{}", program);
+                }
+            }
+            Err(errors) => {
+                succeeded = false;
+                for err in errors {
+                    report(setting, err.to_diagnostic(), file_name, content);
                 }
-                Err(err) => println!(
-                    "Lexer has encountered the following error:\n{}",
-                    err.to_string()
-                ),
             }
         }
     }
+    succeeded
 }
 
 fn main() {
-    print_info();
-
-    let setting = parse_args();
+    let setting = match parse_args() {
+        Ok(setting) => setting,
+        Err(description) => {
+            print_info();
+            eprintln!("{}", description);
+            print_usage();
+            exit(1);
+        }
+    };
+    // Keep the output parseable line by line when errors are printed as JSON.
+    if setting.error_format == ErrorFormat::Human {
+        print_info();
+    }
 
     if setting.input_files.is_empty() {
         print_usage();
         exit(1);
     }
     match read_files(&setting) {
-        Ok(src_contents) => process_src_code(&setting, src_contents),
-        Err(description) => {
-            println!("{}", description);
+        Ok(src_contents) => {
+            if !process_src_code(&setting, src_contents) {
+                exit(1);
+            }
+        }
+        Err((file_name, description)) => {
+            match setting.error_format {
+                ErrorFormat::Human => eprintln!("{}", description),
+                ErrorFormat::Json => {
+                    let diagnostic =
+                        Diagnostic::new(Severity::Error, UNREADABLE_FILE, description, None);
+                    eprintln!("{}", diagnostic.to_file_json(&file_name));
+                }
+            }
             exit(1);
         }
     }
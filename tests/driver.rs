@@ -0,0 +1,78 @@
+use std::process::{Command, Output};
+
+fn run_compiler(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_chia_compiler"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn json_lines(bytes: &[u8]) -> Vec<String> {
+    let lines: Vec<String> = String::from_utf8_lossy(bytes)
+        .lines()
+        .map(String::from)
+        .collect();
+    for line in lines.iter() {
+        assert!(
+            line.starts_with('{') && line.ends_with('}'),
+            "not a JSON object: {}",
+            line
+        );
+    }
+    lines
+}
+
+#[test]
+fn test_driver_json_diagnostics_on_stderr() {
+    let file_path = std::env::temp_dir().join("chia_driver_json_diagnostics.chia");
+    std::fs::write(&file_path, "i32 a = \"open;\ni32 b = ;\n").unwrap();
+    let output = run_compiler(&[
+        "--error-format=json",
+        "--emit=ast",
+        "-v",
+        file_path.to_str().unwrap(),
+    ]);
+    std::fs::remove_file(&file_path).unwrap();
+    assert!(!output.status.success());
+    let lines = json_lines(&output.stderr);
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].contains("\"code\":\"E0002\""));
+    assert!(lines[1].contains("\"code\":\"E0101\""));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("Token: "));
+}
+
+#[test]
+fn test_driver_json_unreadable_file() {
+    let output = run_compiler(&["--error-format=json", "chia_driver_missing_file.chia"]);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let lines = json_lines(&output.stderr);
+    assert_eq!(lines.len(), 1);
+    assert!(lines[0].starts_with("{\"file\":\"chia_driver_missing_file.chia\",\"span\":null,"));
+    assert!(lines[0].contains("\"code\":\"E0800\""));
+}
+
+#[test]
+fn test_driver_human_emit_only_on_stdout() {
+    let file_path = std::env::temp_dir().join("chia_driver_human_emit.chia");
+    std::fs::write(&file_path, "i32 a = 1;\n").unwrap();
+    let output = run_compiler(&["--emit=ast", file_path.to_str().unwrap()]);
+    std::fs::remove_file(&file_path).unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        format!(
+            "Program: {}
+  Variable
+    TypeVarPair
+      Type
+        Identifier: i32
+      Identifier: a
+    Number: 1
+",
+            file_path.to_str().unwrap()
+        )
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("Chia Compiler -- Version: "));
+}